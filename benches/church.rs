@@ -0,0 +1,60 @@
+//! Benchmarks for building and computing with Church numerals.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use m3lc::{to_term, Term};
+
+/// `fn m => fn n => fn f => fn a => m f (n f a)`
+const ADD: &str = "fn m => fn n => fn f => fn a => m f (n f a)";
+
+/// `fn m => fn n => fn f => m (n f)`
+const MUL: &str = "fn m => fn n => fn f => m (n f)";
+
+fn succ_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("succ_chain");
+    for n in [10, 50, 100] {
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            b.iter(|| (0..n).fold(Term::from(0), |acc, _| acc.succ()));
+        });
+    }
+    group.finish();
+}
+
+fn binary_op(c: &mut Criterion, name: &str, op: &str) {
+    let mut group = c.benchmark_group(name);
+    for n in [5, 10, 20] {
+        let term = to_term(&format!("({}) ({}) ({})", op, Term::from(n), Term::from(n)))
+            .expect("valid term");
+        group.bench_with_input(BenchmarkId::from_parameter(n), &term, |b, term| {
+            b.iter(|| term.clone().reduce(false));
+        });
+    }
+    group.finish();
+}
+
+/// Reduce `succ (succ (... 0))`, which is as tall as the numeral it reduces to.
+///
+/// Every step happens deep in the term, so this shows the cost of finding the next redex.
+fn tall_numeral(c: &mut Criterion) {
+    const SUCC: &str = "fn n => fn f => fn a => f (n f a)";
+    let mut group = c.benchmark_group("tall_numeral");
+    for n in [10, 50, 100] {
+        let src = (0..n).fold("fn f => fn a => a".to_string(), |acc, _| {
+            format!("({}) ({})", SUCC, acc)
+        });
+        let term = to_term(&src).expect("valid term");
+        group.bench_with_input(BenchmarkId::from_parameter(n), &term, |b, term| {
+            b.iter(|| term.clone().reduce(false));
+        });
+    }
+    group.finish();
+}
+
+fn add(c: &mut Criterion) {
+    binary_op(c, "add", ADD);
+}
+
+fn church_mul(c: &mut Criterion) {
+    binary_op(c, "church_mul", MUL);
+}
+
+criterion_group!(benches, succ_chain, tall_numeral, add, church_mul);
+criterion_main!(benches);
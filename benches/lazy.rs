@@ -0,0 +1,57 @@
+//! Benchmarks for call-by-need reduction against normal order.
+//!
+//! These terms use their arguments many times, which is where sharing should pay off: `reduce`
+//! reduces every copy of the argument, and `reduce_lazy` reduces it once.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use m3lc::Term;
+
+const SIZES: [usize; 3] = [2, 8, 32];
+
+/// `(fn x => f x ... x) (3 3)`, with `n` uses of an argument that takes a while to reduce.
+fn many_uses(n: usize) -> Term {
+    let body = Term::apply_all("f".into(), vec!["x".into(); n]);
+    let arg = Term::apply_all(3.into(), vec![3.into()]);
+    Term::apply_all(Term::lambda(&["x"], body), vec![arg])
+}
+
+/// `(fn d => d (d (... (d 1)))) (fn n => add n n)`, doubling `n` times.
+///
+/// `add n n` uses `n` twice, so normal order's work doubles with every `d`, where call-by-need's
+/// only grows with the size of the numeral.
+fn repeated_doubling(n: usize) -> Term {
+    let add = Term::lambda(
+        &["m", "k", "f", "a"],
+        Term::apply_all(
+            "m".into(),
+            vec!["f".into(), Term::apply_all("k".into(), vec!["f".into(), "a".into()])],
+        ),
+    );
+    let double = Term::lambda(&["n"], Term::apply_all(add, vec!["n".into(), "n".into()]));
+    let body = (0..n).fold(Term::from(1), |acc, _| Term::apply_all("d".into(), vec![acc]));
+    Term::apply_all(Term::lambda(&["d"], body), vec![double])
+}
+
+fn compare(c: &mut Criterion, name: &str, build: fn(usize) -> Term, sizes: &[usize]) {
+    let mut group = c.benchmark_group(name);
+    for &n in sizes {
+        let term = build(n);
+        group.bench_with_input(BenchmarkId::new("normal", n), &term, |b, term| {
+            b.iter(|| term.clone().reduce(false));
+        });
+        group.bench_with_input(BenchmarkId::new("lazy", n), &term, |b, term| {
+            b.iter(|| term.reduce_lazy());
+        });
+    }
+    group.finish();
+}
+
+fn uses(c: &mut Criterion) {
+    compare(c, "many_uses", many_uses, &SIZES);
+}
+
+fn doubling(c: &mut Criterion) {
+    compare(c, "repeated_doubling", repeated_doubling, &[2, 4, 6]);
+}
+
+criterion_group!(benches, uses, doubling);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+//! Benchmarks for substitution-heavy reductions.
+//!
+//! Each group reduces the same terms with both `Term`, whose `subst` works on references to avoid
+//! clones, and `SharedTerm`, which shares untouched subterms instead. Run these before and after
+//! changing either substitution to see whether it actually helped.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use m3lc::Term;
+
+const SIZES: [usize; 3] = [10, 50, 100];
+
+/// The names `{prefix}1` through `{prefix}n`.
+fn names(prefix: &str, n: usize) -> Vec<String> {
+    (1..=n).map(|i| format!("{}{}", prefix, i)).collect()
+}
+
+/// `(fn x => fn a1 => ... fn an => x) y`, where the substitution has to walk down `n` lambdas.
+fn deep_nesting(n: usize) -> Term {
+    let params = names("a", n);
+    let params: Vec<_> = params.iter().map(String::as_str).collect();
+    let body = Term::lambda(&params, "x".into());
+    Term::apply_all(Term::lambda(&["x"], body), vec!["y".into()])
+}
+
+/// `(fn x => f x ... x) (fn z => z)`, with `n` occurrences of `x` to replace.
+fn many_occurrences(n: usize) -> Term {
+    let body = Term::apply_all("f".into(), vec!["x".into(); n]);
+    Term::apply_all(Term::lambda(&["x"], body), vec![Term::lambda(&["z"], "z".into())])
+}
+
+/// `(fn x => fn y1 => ... fn yn => x) (y1 ... yn)`, where every param has to be renamed so it
+/// doesn't capture anything in the argument.
+fn many_renames(n: usize) -> Term {
+    let params = names("y", n);
+    let params: Vec<_> = params.iter().map(String::as_str).collect();
+    let body = Term::lambda(&params, "x".into());
+    let args: Vec<Term> = params.iter().map(|&param| param.into()).collect();
+    let (head, tail) = args.split_first().expect("n is positive");
+    let arg = Term::apply_all(head.clone(), tail.to_vec());
+    Term::apply_all(Term::lambda(&["x"], body), vec![arg])
+}
+
+fn compare(c: &mut Criterion, name: &str, build: fn(usize) -> Term) {
+    let mut group = c.benchmark_group(name);
+    for n in SIZES {
+        let term = build(n);
+        group.bench_with_input(BenchmarkId::new("box", n), &term, |b, term| {
+            b.iter(|| term.clone().reduce(false));
+        });
+        group.bench_with_input(BenchmarkId::new("shared", n), &term, |b, term| {
+            b.iter(|| term.shared().reduce());
+        });
+    }
+    group.finish();
+}
+
+fn nesting(c: &mut Criterion) {
+    compare(c, "deep_nesting", deep_nesting);
+}
+
+fn occurrences(c: &mut Criterion) {
+    compare(c, "many_occurrences", many_occurrences);
+}
+
+fn renames(c: &mut Criterion) {
+    compare(c, "many_renames", many_renames);
+}
+
+criterion_group!(benches, nesting, occurrences, renames);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+//! Random terms, for property testing with `proptest`.
+use proptest::{
+    arbitrary::Arbitrary,
+    prelude::*,
+    sample::select,
+    strategy::{BoxedStrategy, Strategy},
+};
+
+use crate::grammar::Term;
+
+/// The names generated terms use, so that they're likely to refer to each other.
+const NAMES: [&str; 3] = ["x", "y", "z"];
+
+/// How to generate terms, for `any_with::<Term>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermParams {
+    /// How many levels of lambdas and applications to nest, at most.
+    pub depth: u32,
+    /// Roughly how many nodes to aim for.
+    pub size: u32,
+    /// Whether to bind every name, so the term has no free vars.
+    pub closed: bool,
+}
+
+impl Default for TermParams {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            size: 32,
+            closed: false,
+        }
+    }
+}
+
+impl Arbitrary for Term {
+    type Parameters = TermParams;
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Generate terms over a few names, with lambdas and applications in every position.
+    ///
+    /// A third of the compound terms are redexes, which stresses the reducer far more than
+    /// uniformly random terms would, since those rarely reduce at all.
+    fn arbitrary_with(params: Self::Parameters) -> Self::Strategy {
+        let names = || select(NAMES.to_vec());
+        let terms = names().prop_map(Self::from).prop_recursive(
+            params.depth,
+            params.size,
+            2,
+            move |inner| {
+                prop_oneof![
+                    (names(), inner.clone()).prop_map(|(p, rule)| Self::lambda(&[p], rule)),
+                    (inner.clone(), inner.clone())
+                        .prop_map(|(left, right)| Self::apply_all(left, vec![right])),
+                    (names(), inner.clone(), inner).prop_map(|(p, rule, right)| {
+                        Self::apply_all(Self::lambda(&[p], rule), vec![right])
+                    }),
+                ]
+            },
+        );
+        if params.closed {
+            terms.prop_map(|body| Self::lambda(&NAMES, body)).boxed()
+        } else {
+            terms.boxed()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::TermParams;
+    use crate::grammar::Term;
+
+    proptest! {
+        #[test]
+        fn closed(term in any_with::<Term>(TermParams { closed: true, ..TermParams::default() })) {
+            prop_assert!(term.is_closed());
+        }
+
+        #[test]
+        fn within_depth(term in any::<Term>()) {
+            let depth = term.fold(|_| 0, |_, rule| rule + 1, |left, right| left.max(right) + 1);
+            prop_assert!(depth <= 2 * TermParams::default().depth);
+        }
+    }
+}
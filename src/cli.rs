@@ -1,13 +1,30 @@
 //! The command-line interface.
 
-use std::{fmt::Display, fs};
+use std::{
+    env,
+    ffi::OsString,
+    fmt::Display,
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
 
-use crate::{to_file, ParserResult, Term};
+#[cfg(feature = "serde")]
+use crate::ParserError;
+use crate::{data::combinator::NAMED, lint_source, to_file_in_dir, CleanNames, ParserResult, Term};
 use colored::{ColoredString, Colorize};
+#[cfg(feature = "serde")]
+use pest::{error::ErrorVariant, Position};
 use structopt::StructOpt;
 
+/// How many steps to spend normalizing each defn before substituting it as written instead.
+const MAX_DEFN_STEPS: usize = 10_000;
+
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
+#[allow(clippy::struct_excessive_bools)] // they're flags
 struct Opt {
     /// Input file
     file: String,
@@ -19,12 +36,99 @@ struct Opt {
     /// Don't attempt to determine the output value
     #[structopt(short, long)]
     no_inference: bool,
+
+    /// Print the size of the term before each beta-reduction step
+    #[structopt(long)]
+    trace_size: bool,
+
+    /// Give up reducing after this many milliseconds, and print the term so far
+    #[structopt(long, conflicts_with_all = &["verbose", "trace-size"])]
+    timeout_ms: Option<u64>,
+
+    /// Give up reducing once the term has more than this many nodes, and print the term so far
+    #[structopt(long, conflicts_with = "timeout-ms")]
+    max_size: Option<usize>,
+
+    /// Print how many steps the reduction took and how big the term got
+    #[structopt(long, conflicts_with_all = &["verbose", "trace-size", "timeout-ms", "max-size"])]
+    stats: bool,
+
+    /// Print only the first this many beta-reduction steps, then the term so far
+    #[structopt(
+        long,
+        conflicts_with_all = &["verbose", "trace-size", "timeout-ms", "max-size", "stats"]
+    )]
+    steps: Option<usize>,
+
+    /// When to color the output: `never`, `always`, or `auto`, which colors it when stdout is a
+    /// terminal and `NO_COLOR` isn't set
+    #[structopt(long, default_value = "auto", possible_values = &["never", "always", "auto"])]
+    color: ColorChoice,
+
+    /// Print the output with the fresh names reduction makes, like `x.12`, instead of tidying
+    /// them up
+    #[structopt(long)]
+    raw_names: bool,
+
+    /// Just print the file in canonical format, without reducing it
+    #[structopt(long)]
+    fmt: bool,
+
+    /// Just print the parsed file's syntax tree, as JSON if built with `serde`, without reducing
+    /// it
+    #[structopt(long, conflicts_with = "fmt")]
+    dump_ast: bool,
+
+    /// Print the reduced term as JSON
+    #[cfg(feature = "serde")]
+    #[structopt(long)]
+    emit_json: bool,
+
+    /// Read the input file as a JSON term, as printed by `--emit-json`
+    #[cfg(feature = "serde")]
+    #[structopt(long, conflicts_with_all = &["fmt", "dump-ast"])]
+    from_json: bool,
+}
+
+/// When to color the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorChoice {
+    Never,
+    Always,
+    Auto,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(Self::Never),
+            "always" => Ok(Self::Always),
+            "auto" => Ok(Self::Auto),
+            _ => Err(format!("invalid color choice `{}`", s)),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Decide whether to color, given the value of `NO_COLOR` and whether stdout is a terminal.
+    ///
+    /// Following <https://no-color.org>, `NO_COLOR` only counts if it's set to something
+    /// non-empty.
+    fn should_color(self, no_color: Option<OsString>, is_terminal: bool) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Always => true,
+            Self::Auto => no_color.is_none_or(|v| v.is_empty()) && is_terminal,
+        }
+    }
 }
 
 impl Term {
     /// Guess the value of the term.
     ///
-    /// Currently, supports Church numerals and booleans.
+    /// Currently, supports Church numerals, booleans, and some well-known combinators.
     fn guess_val(&self) -> Matches {
         vec![
             self.try_into()
@@ -34,11 +138,41 @@ impl Term {
         ]
         .into_iter()
         .flatten()
+        .chain(
+            NAMED
+                .iter()
+                .filter(|(_, term)| self.alpha_equiv(term))
+                .map(|(name, _)| (*name).to_string()),
+        )
         .map(|s| s.green())
         .collect()
     }
 }
 
+impl Term {
+    /// Explain why the term is stuck, if it's stuck on a free variable.
+    ///
+    /// In a normal form, a free var in head position is almost always an undefined name, since
+    /// nothing can ever be substituted for it. Free vars elsewhere might be intended, so those
+    /// only get mentioned alongside a stuck head.
+    fn stuck_warning(&self) -> Option<String> {
+        let head = self.stuck_head()?;
+        let mut others: Vec<_> = self
+            .free_vars()
+            .into_iter()
+            .filter(|x| *x != head)
+            .map(|x| format!("`{}`", x))
+            .collect();
+        others.sort();
+        let warning = format!("reduction got stuck on `{}`; is it defined?", head);
+        Some(if others.is_empty() {
+            warning
+        } else {
+            format!("{} (other free variables: {})", warning, others.join(", "))
+        })
+    }
+}
+
 struct Matches {
     matches: Vec<ColoredString>,
 }
@@ -73,18 +207,177 @@ impl Display for Matches {
     }
 }
 
+/// Reduce the term the way the flags ask for.
+fn reduce(opt: &Opt, mut output: Term) -> Term {
+    if opt.verbose || opt.trace_size {
+        // like `reduce(true)`, but highlight the redex each step contracts; each line is only
+        // printed once the step finds a redex, so the normal form isn't
+        loop {
+            let line = match (opt.trace_size, opt.verbose) {
+                (true, true) => format!(
+                    "[size={}] {}",
+                    output.size(),
+                    output.display_with_redex_highlight()
+                ),
+                (true, false) => format!("[size={}]", output.size()),
+                _ => output.display_with_redex_highlight(),
+            };
+            if !output.reduction_step() {
+                break;
+            }
+            println!("{}", line);
+        }
+    } else if let Some(ms) = opt.timeout_ms {
+        output = match output.reduce_timeout(Duration::from_millis(ms), false) {
+            Ok(output) => output,
+            Err(output) => {
+                eprintln!(
+                    "{} gave up after {} ms; printing the term so far",
+                    "warning:".yellow().bold(),
+                    ms
+                );
+                output
+            }
+        };
+    } else if let Some(max_size) = opt.max_size {
+        output = match output.reduce_size_capped(max_size, false) {
+            Ok(output) => output,
+            Err(output) => {
+                eprintln!(
+                    "{} gave up once the term grew past {} nodes; printing the term so far",
+                    "warning:".yellow().bold(),
+                    max_size
+                );
+                output
+            }
+        };
+    } else if let Some(steps) = opt.steps {
+        // like `verbose`, but stopping early on purpose
+        for _ in 0..steps {
+            let line = output.display_with_redex_highlight();
+            if !output.reduction_step() {
+                return output;
+            }
+            println!("{}", line);
+        }
+        if !output.is_irreducible() {
+            eprintln!(
+                "{} stopped after {} steps, as asked; printing the term so far",
+                "note:".bold(),
+                steps
+            );
+        }
+    } else if opt.stats {
+        let (normal, stats) = output.reduce_stats(usize::MAX);
+        output = normal.expect("an unbounded reduction only returns at a normal form");
+        eprintln!("{} {}", "stats:".bold(), stats);
+    } else {
+        output.reduce_in_place(false);
+    }
+    output
+}
+
+/// Read a term printed by `--emit-json`, reporting bad JSON like any other invalid input.
+#[cfg(feature = "serde")]
+fn from_json(contents: &str) -> ParserResult<Term> {
+    serde_json::from_str(contents).map_err(|e| {
+        let message = format!("invalid JSON term: {}", e);
+        ParserError::new_from_pos(
+            ErrorVariant::CustomError { message },
+            Position::from_start(contents),
+        )
+    })
+}
+
 /// Run the CLI.
 ///
 /// # Errors
 /// Returns `ParserResult` if passed an invalid term.
 pub fn run() -> ParserResult<()> {
     let opt = Opt::from_args();
+    colored::control::set_override(
+        opt.color
+            .should_color(env::var_os("NO_COLOR"), io::stdout().is_terminal()),
+    );
 
     let contents = fs::read_to_string(&opt.file).expect("Unable to open file");
-    let input = to_file(&contents)?;
 
-    let output = input.unroll().reduce(opt.verbose);
-    println!("{}", &output);
+    #[cfg(feature = "serde")]
+    let json_input = if opt.from_json {
+        Some(from_json(&contents)?)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "serde"))]
+    let json_input = None;
+
+    let output = if let Some(term) = json_input {
+        term
+    } else {
+        // includes are relative to the input file
+        let dir = Path::new(&opt.file)
+            .parent()
+            .unwrap_or_else(|| Path::new("."));
+        let input = to_file_in_dir(&contents, dir)?;
+        if opt.fmt {
+            println!("{}", input);
+            return Ok(());
+        }
+        if opt.dump_ast {
+            #[cfg(feature = "serde")]
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&input).expect("files always serialize")
+            );
+            #[cfg(not(feature = "serde"))]
+            println!("{:#?}", input);
+            return Ok(());
+        }
+        for warning in lint_source(&contents)? {
+            eprintln!("{} {}", "warning:".yellow().bold(), warning);
+        }
+        for defn in input.defns().iter().filter(|defn| !defn.check_arity()) {
+            eprintln!(
+                "{} `{}` is annotated with arity {}, but doesn't take that many arguments",
+                "warning:".yellow().bold(),
+                defn.name(),
+                defn.arity().expect("unannotated defns always check")
+            );
+        }
+        if opt.verbose
+            || opt.trace_size
+            || opt.stats
+            || opt.steps.is_some()
+            || opt.timeout_ms.is_some()
+            || opt.max_size.is_some()
+        {
+            // the steps are easier to follow when the defns are visibly substituted in, and
+            // `File::reduce` can't be interrupted, or counted
+            input.unroll()
+        } else {
+            input.reduce(MAX_DEFN_STEPS)
+        }
+    };
+    let output = reduce(&opt, output);
+
+    #[cfg(feature = "serde")]
+    if opt.emit_json {
+        println!(
+            "{}",
+            serde_json::to_string(&output).expect("terms always serialize")
+        );
+        return Ok(());
+    }
+
+    let names = if opt.raw_names {
+        CleanNames::Keep
+    } else {
+        CleanNames::StripSuffixes
+    };
+    println!("{}", output.clone().clean_names(names));
+    if let Some(warning) = output.stuck_warning() {
+        eprintln!("{} {}", "warning:".yellow().bold(), warning);
+    }
 
     if !opt.no_inference {
         let guessed_value = output.guess_val();
@@ -97,3 +390,82 @@ pub fn run() -> ParserResult<()> {
 }
 
 // TODO: test this lol
+
+#[cfg(test)]
+mod tests {
+    mod color {
+        use super::super::ColorChoice;
+
+        #[test]
+        fn never_and_always() {
+            assert!(!ColorChoice::Never.should_color(None, true));
+            assert!(ColorChoice::Always.should_color(Some("1".into()), false));
+        }
+
+        #[test]
+        fn auto() {
+            assert!(ColorChoice::Auto.should_color(None, true));
+            assert!(!ColorChoice::Auto.should_color(None, false));
+            assert!(!ColorChoice::Auto.should_color(Some("1".into()), true));
+            // an empty `NO_COLOR` doesn't count
+            assert!(ColorChoice::Auto.should_color(Some("".into()), true));
+        }
+
+        #[test]
+        fn parse() {
+            assert_eq!("never".parse(), Ok(ColorChoice::Never));
+            assert!("sometimes".parse::<ColorChoice>().is_err());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod from_json {
+        use super::super::from_json;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn round_trip() -> ParserResult<()> {
+            let term = to_term("fn x => x y")?;
+            let json = serde_json::to_string(&term).expect("terms always serialize");
+            assert_eq!(from_json(&json)?, term);
+            Ok(())
+        }
+
+        #[test]
+        /// Bad JSON is an error, not a panic.
+        fn invalid() {
+            assert!(from_json("{\"Var\": ").is_err());
+            assert!(from_json("{\"Lam\": 3}").is_err());
+        }
+    }
+
+    mod stuck_warning {
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn normal_form() -> ParserResult<()> {
+            assert_eq!(to_term("fn f => f x")?.stuck_warning(), None);
+            Ok(())
+        }
+
+        #[test]
+        fn stuck() -> ParserResult<()> {
+            let warning = to_term("undefined (fn f => fn a => a)")?.stuck_warning();
+            assert_eq!(
+                warning.as_deref(),
+                Some("reduction got stuck on `undefined`; is it defined?")
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn other_free_vars() -> ParserResult<()> {
+            let warning = to_term("g y (fn a => x)")?.stuck_warning();
+            assert_eq!(
+                warning.as_deref(),
+                Some("reduction got stuck on `g`; is it defined? (other free variables: `x`, `y`)")
+            );
+            Ok(())
+        }
+    }
+}
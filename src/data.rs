@@ -1,4 +1,10 @@
 //! Contains specific lambda terms.
 
+pub mod ascii;
 pub mod bool;
 pub mod church;
+pub mod combinator;
+pub mod either;
+pub mod list;
+pub mod option;
+pub mod programs;
@@ -0,0 +1,75 @@
+//! Strings, as lists of their bytes' Church numerals.
+use crate::grammar::Term;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+impl Term {
+    /// Encode a string as a list of the Church numerals of its bytes.
+    ///
+    /// For ASCII strings, that's one numeral per character, its ASCII code.
+    #[must_use]
+    pub fn from_ascii_string(s: &str) -> Self {
+        Self::from_list(s.bytes().map(|b| usize::from(b).into()).collect())
+    }
+
+    /// Decode a list of byte numerals back to a string.
+    ///
+    /// Returns `None` if this isn't a list, any element isn't a numeral in `0..=255`, or the bytes
+    /// aren't valid UTF-8.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let hi = Term::from_ascii_string("hi");
+    /// assert_eq!(hi.to_ascii_string(), Some("hi".to_string()));
+    /// ```
+    #[must_use]
+    pub fn to_ascii_string(&self) -> Option<String> {
+        let bytes = self
+            .to_list()?
+            .iter()
+            .map(|item| {
+                usize::try_from(item)
+                    .ok()
+                    .and_then(|n| u8::try_from(n).ok())
+            })
+            .collect::<Option<_>>()?;
+        String::from_utf8(bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data::list::CONS, grammar::Term};
+
+    #[test]
+    fn round_trip() {
+        for s in ["", "hello, world!", "\n\t~"] {
+            assert_eq!(
+                Term::from_ascii_string(s).to_ascii_string().as_deref(),
+                Some(s)
+            );
+        }
+    }
+
+    #[test]
+    /// A string built by a lambda program decodes.
+    fn reduced() {
+        let hi = Term::apply_all(
+            CONS.clone(),
+            vec![usize::from(b'h').into(), Term::from_ascii_string("i")],
+        );
+        assert_eq!(hi.reduce(false).to_ascii_string().as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(Term::from_list(vec![256.into()]).to_ascii_string(), None);
+    }
+
+    #[test]
+    fn not_a_numeral() {
+        assert_eq!(Term::from_list(vec!["x".into()]).to_ascii_string(), None);
+        assert_eq!(Term::from(3).to_ascii_string(), None);
+    }
+}
@@ -1,11 +1,13 @@
 //! Booleans.
+use core::fmt::Display;
+
 use lazy_static::lazy_static;
 
 use crate::grammar::Term;
 use Term::{Appl, Lam};
 
 lazy_static! {
-    static ref TRUE: Term = Lam {
+    pub(crate) static ref TRUE: Term = Lam {
         param: "t".into(),
         rule: Lam {
             param: "e".into(),
@@ -13,7 +15,7 @@ lazy_static! {
         }
         .into()
     };
-    static ref FALSE: Term = Lam {
+    pub(crate) static ref FALSE: Term = Lam {
         param: "t".into(),
         rule: Lam {
             param: "e".into(),
@@ -21,7 +23,7 @@ lazy_static! {
         }
         .into()
     };
-    static ref AND: Term = Lam {
+    pub(crate) static ref AND: Term = Lam {
         param: "a".into(),
         rule: Lam {
             param: "b".into(),
@@ -49,13 +51,28 @@ impl From<bool> for Term {
     }
 }
 
+/// How many steps `Term::as_bool` takes before giving up.
+const AS_BOOL_STEPS: usize = 10_000;
+
 /// The `Term` is not Boolean.
 #[derive(Debug)]
 pub struct NotBoolean;
 
+impl Display for NotBoolean {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "term is not a boolean")
+    }
+}
+
+impl core::error::Error for NotBoolean {}
+
 impl TryFrom<&Term> for bool {
     type Error = NotBoolean;
 
+    /// Decode a boolean in normal form.
+    ///
+    /// This is strict: a term that only reduces to a boolean, like `(fn x => x) true`, is an
+    /// error. See `Term::as_bool` for a version that reduces first.
     fn try_from(term: &Term) -> Result<Self, Self::Error> {
         if term.alpha_equiv(&*TRUE) {
             Ok(true)
@@ -68,6 +85,28 @@ impl TryFrom<&Term> for bool {
 }
 
 impl Term {
+    /// Decode a term that computes a boolean.
+    ///
+    /// Unlike `bool::try_from`, this reduces a clone of the term first, giving up after
+    /// `AS_BOOL_STEPS` steps, so it also recognizes terms that aren't in normal form yet.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => x) (fn t => fn e => t)")?;
+    /// assert_eq!(term.as_bool(), Some(true));
+    /// assert!(bool::try_from(&term).is_err());
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        let normal = self.clone().reduce_bounded(AS_BOOL_STEPS).ok()?;
+        bool::try_from(&normal).ok()
+    }
+
     #[must_use]
     pub fn and(self, other: Self) -> Self {
         Appl {
@@ -105,4 +144,26 @@ mod tests {
     fn false_and_false() {
         assert!(FALSE.clone().and(FALSE.clone()).alpha_equiv(&*FALSE));
     }
+
+    #[test]
+    fn as_bool() {
+        let id = Term::lambda(&["x"], "x".into());
+        let term = Term::apply_all(id, vec![FALSE.clone()]);
+        assert_eq!(term.as_bool(), Some(false));
+        assert_eq!(TRUE.as_bool(), Some(true));
+        assert_eq!(Term::from(2).as_bool(), None);
+    }
+
+    #[test]
+    fn as_bool_diverges() {
+        let half = Term::lambda(&["x"], Term::apply_all("x".into(), vec!["x".into()]));
+        let omega = Term::apply_all(half.clone(), vec![half]);
+        assert_eq!(omega.as_bool(), None);
+    }
+
+    #[test]
+    fn not_boolean() {
+        let err = bool::try_from(&Term::from("x")).unwrap_err();
+        assert_eq!(err.to_string(), "term is not a boolean");
+    }
 }
@@ -1,11 +1,25 @@
 //! The Church numerals.
+use core::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Div, Mul, Rem, Sub},
+};
+
 use lazy_static::lazy_static;
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
 
+use super::{
+    bool::{AND, FALSE, TRUE},
+    combinator::Y,
+};
 use crate::grammar::Term;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use Term::{Appl, Lam, Var};
 
 lazy_static! {
-    static ref SUCC: Term = Lam {
+    pub(crate) static ref SUCC: Term = Lam {
         param: "n".into(),
         rule: Lam {
             param: "f".into(),
@@ -29,6 +43,97 @@ lazy_static! {
         }
         .into()
     };
+
+    /// `fn m => fn n => fn f => fn a => m f (n f a)`
+    pub(crate) static ref ADD: Term = Term::lambda(
+        &["m", "n", "f", "a"],
+        Term::apply_all(
+            "m".into(),
+            vec![
+                "f".into(),
+                Term::apply_all("n".into(), vec!["f".into(), "a".into()])
+            ]
+        )
+    );
+
+    /// `fn m => fn n => fn f => m (n f)`
+    pub(crate) static ref MUL: Term = Term::lambda(
+        &["m", "n", "f"],
+        Term::apply_all(
+            "m".into(),
+            vec![Term::apply_all("n".into(), vec!["f".into()])]
+        )
+    );
+
+    /// `fn n => n (fn x => false) true`
+    pub(crate) static ref IS_ZERO: Term = Term::lambda(
+        &["n"],
+        Term::apply_all(
+            "n".into(),
+            vec![Term::lambda(&["x"], FALSE.clone()), TRUE.clone()]
+        )
+    );
+
+    /// `fn n => fn f => fn a => n (fn g => fn h => h (g f)) (fn u => a) (fn u => u)`
+    pub(crate) static ref PRED: Term = Term::lambda(
+        &["n", "f", "a"],
+        Term::apply_all(
+            "n".into(),
+            vec![
+                Term::lambda(
+                    &["g", "h"],
+                    Term::apply_all(
+                        "h".into(),
+                        vec![Term::apply_all("g".into(), vec!["f".into()])]
+                    )
+                ),
+                Term::lambda(&["u"], "a".into()),
+                Term::lambda(&["u"], "u".into()),
+            ]
+        )
+    );
+
+    /// `fn m => fn n => n pred m`
+    static ref SUB: Term = Term::lambda(
+        &["m", "n"],
+        Term::apply_all("n".into(), vec![PRED.clone(), "m".into()])
+    );
+
+    /// `fn m => fn n => is_zero n 0 (y (fn d => fn m => fn n => is_zero (sub n m) (succ (d (sub
+    /// m n) n)) 0) m n)`
+    static ref DIV: Term = {
+        let body = Term::apply_all(
+            SUCC.clone(),
+            vec![Term::apply_all("d".into(), vec![sub("m", "n"), "n".into()])],
+        );
+        divide(body, 0.into())
+    };
+
+    /// `fn m => fn n => is_zero n m (y (fn d => fn m => fn n => is_zero (sub n m) (d (sub m n)
+    /// n) m) m n)`
+    static ref REM: Term = {
+        let body = Term::apply_all("d".into(), vec![sub("m", "n"), "n".into()]);
+        divide(body, "m".into())
+    };
+}
+
+/// `sub m n`, for the named vars `m` and `n`.
+fn sub(m: &str, n: &str) -> Term {
+    Term::apply_all(SUB.clone(), vec![m.into(), n.into()])
+}
+
+/// Build division or remainder by repeated subtraction.
+///
+/// The loop `d` takes `m` and `n`, and while `m >= n` computes `body`, which refers to `d`, `m`,
+/// and `n`. Once `m < n`, it returns `base`, which can refer to `m`. The term returns `base`
+/// right away if `n` is zero, instead of diverging.
+fn divide(body: Term, base: Term) -> Term {
+    let is_zero = |n: Term, then: Term, otherwise: Term| {
+        Term::apply_all(IS_ZERO.clone(), vec![n, then, otherwise])
+    };
+    let step = Term::lambda(&["d", "m", "n"], is_zero(sub("n", "m"), body, base.clone()));
+    let looped = Term::apply_all(Y.clone(), vec![step, "m".into(), "n".into()]);
+    Term::lambda(&["m", "n"], is_zero("n".into(), base, looped))
 }
 
 impl Term {
@@ -52,26 +157,319 @@ impl Term {
         }
         .reduce(false)
     }
+
+    /// Compute the predecessor of n, where the predecessor of zero is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let three: Term = 3.into();
+    /// assert!(three.pred().alpha_equiv(&2.into()));
+    /// ```
+    #[must_use]
+    pub fn pred(self) -> Self {
+        Self::apply_all(PRED.clone(), vec![self]).reduce(false)
+    }
+
+    /// Compute `self - other`, truncated at zero, like `usize::saturating_sub`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let five: Term = 5.into();
+    /// assert!(five.saturating_sub(3.into()).alpha_equiv(&2.into()));
+    /// ```
+    #[must_use]
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self::apply_all(SUB.clone(), vec![self, other]).reduce(false)
+    }
+
+    /// Compute `self / other`, rounding down, reduced to normal form.
+    ///
+    /// This is repeated subtraction, so it takes time proportional to the quotient. Dividing by
+    /// zero gives zero, instead of diverging. The `/` operator does the same thing.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let three = Term::div(7.into(), 2.into());
+    /// assert!(three.alpha_equiv(&3.into()));
+    /// assert!((Term::from(7) / Term::from(0)).alpha_equiv(&0.into()));
+    /// ```
+    #[must_use]
+    #[allow(clippy::should_implement_trait)] // it does, but this works without importing `Div`
+    pub fn div(self, other: Self) -> Self {
+        Self::apply_all(DIV.clone(), vec![self, other]).reduce(false)
+    }
+
+    /// Compute the remainder of `self / other`, reduced to normal form.
+    ///
+    /// Like `div`, this is repeated subtraction. The remainder after dividing by zero is the
+    /// dividend, so that `(m / n) * n + m % n == m` still holds. The `%` operator does the same
+    /// thing.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let one = Term::rem(7.into(), 2.into());
+    /// assert!(one.alpha_equiv(&1.into()));
+    /// assert!((Term::from(7) % Term::from(0)).alpha_equiv(&7.into()));
+    /// ```
+    #[must_use]
+    #[allow(clippy::should_implement_trait)] // likewise for `Rem`
+    pub fn rem(self, other: Self) -> Self {
+        Self::apply_all(REM.clone(), vec![self, other]).reduce(false)
+    }
+
+    /// Check whether n is zero, producing a Church boolean.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let zero: Term = 0.into();
+    /// assert!(zero.is_zero().alpha_equiv(&true.into()));
+    /// ```
+    #[must_use]
+    pub fn is_zero(self) -> Self {
+        Self::apply_all(IS_ZERO.clone(), vec![self]).reduce(false)
+    }
+
+    /// Check whether two Church numerals are equal, producing a Church boolean.
+    ///
+    /// This is `and (is_zero (sub m n)) (is_zero (sub n m))`, i.e. it's computed entirely in the
+    /// lambda calculus; see `church_cmp` for a version that works on the terms directly.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let three: Term = 3.into();
+    /// assert!(three.church_eq(3.into()).alpha_equiv(&true.into()));
+    /// ```
+    #[must_use]
+    pub fn church_eq(self, other: Self) -> Self {
+        let le = |m: Self, n: Self| {
+            Self::apply_all(
+                IS_ZERO.clone(),
+                vec![Self::apply_all(SUB.clone(), vec![m, n])],
+            )
+        };
+        Self::apply_all(
+            AND.clone(),
+            vec![le(self.clone(), other.clone()), le(other, self)],
+        )
+        .reduce(false)
+    }
+}
+
+// The arithmetic operators treat terms as Church numerals, and reduce the result right away, like
+// `succ` and friends. On other terms they still build and reduce the application, which may
+// not mean anything, or terminate.
+
+impl Add for Term {
+    type Output = Self;
+
+    /// Church addition, reduced to normal form.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let six = Term::from(2) + Term::from(4);
+    /// assert!(six.alpha_equiv(&6.into()));
+    /// ```
+    fn add(self, other: Self) -> Self {
+        Self::apply_all(ADD.clone(), vec![self, other]).reduce(false)
+    }
+}
+
+impl Mul for Term {
+    type Output = Self;
+
+    /// Church multiplication, reduced to normal form.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let six = Term::from(2) * Term::from(3);
+    /// assert!(six.alpha_equiv(&6.into()));
+    /// ```
+    fn mul(self, other: Self) -> Self {
+        Self::apply_all(MUL.clone(), vec![self, other]).reduce(false)
+    }
+}
+
+impl Sub for Term {
+    type Output = Self;
+
+    /// Church subtraction, truncated at zero; see `saturating_sub`.
+    fn sub(self, other: Self) -> Self {
+        self.saturating_sub(other)
+    }
+}
+
+impl Div for Term {
+    type Output = Self;
+
+    /// Church division, rounding down; see `Term::div`.
+    fn div(self, other: Self) -> Self {
+        Self::div(self, other)
+    }
+}
+
+impl Rem for Term {
+    type Output = Self;
+
+    /// The remainder of Church division; see `Term::rem`.
+    fn rem(self, other: Self) -> Self {
+        Self::rem(self, other)
+    }
+}
+
+impl Term {
+    /// Build a term that computes the Church numeral `base ^ exp`.
+    ///
+    /// This is `fn f => fn a => exp base f a`, i.e. the Church exponentiation of two small
+    /// numerals, so it's only about `base + exp` nodes, compared to the `base ^ exp` nodes of the
+    /// numeral itself. Reduce it to get the numeral. (`exp base` alone is only eta-equivalent to
+    /// the numeral when `exp` is zero, hence the extra lambdas.)
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let big = Term::church_via_exp(2, 10).reduce(false);
+    /// assert_eq!(usize::try_from(&big).ok(), Some(1024));
+    /// ```
+    #[must_use]
+    pub fn church_via_exp(base: usize, exp: usize) -> Self {
+        Self::lambda(
+            &["f", "a"],
+            Self::apply_all(exp.into(), vec![base.into(), "f".into(), "a".into()]),
+        )
+    }
+}
+
+/// Build the numeral with one `f` per item of `steps`.
+fn numeral(steps: impl Iterator) -> Term {
+    // Imperative instead of recursive to avoid repeated clones of `SUCC` and so we can use
+    // this to test `succ`.
+    let mut out: Term = "a".into();
+    for _ in steps {
+        out = Appl {
+            left: "f".into(),
+            right: out.into(),
+        };
+    }
+    Lam {
+        param: "f".into(),
+        rule: Lam {
+            param: "a".into(),
+            rule: out.into(),
+        }
+        .into(),
+    }
 }
 
 impl From<usize> for Term {
     fn from(n: usize) -> Self {
-        // Imperative instead of recursive to avoid repeated clones of `SUCC` and so we can use
-        // this to test `succ`.
-        let mut out: Self = "a".into();
-        for _ in 0..n {
-            out = Appl {
-                left: "f".into(),
-                right: out.into(),
-            };
+        numeral(0..n)
+    }
+}
+
+// There are deliberately no `From` impls for the other integer types: with more than one, the
+// type of a literal in `3.into()` can't be inferred any more.
+
+impl TryFrom<u64> for Term {
+    type Error = NumeralTooLarge;
+
+    /// Build a numeral, failing if it would have more than `usize::MAX` nodes.
+    fn try_from(n: u64) -> Result<Self, Self::Error> {
+        Self::numeral_within(n, usize::MAX)
+    }
+}
+
+impl TryFrom<u32> for Term {
+    type Error = NumeralTooLarge;
+
+    fn try_from(n: u32) -> Result<Self, Self::Error> {
+        Self::try_from(u64::from(n))
+    }
+}
+
+impl Term {
+    /// Build the numeral `n`, unless it would have more than `max_size` nodes.
+    ///
+    /// The numeral `n` has `2n + 3` nodes, so this checks before allocating anything, which
+    /// guards against accidentally building a gigantic term.
+    ///
+    /// # Errors
+    /// Returns `NumeralTooLarge` if the numeral would be bigger than `max_size`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// assert_eq!(Term::numeral_within(3, 9).ok(), Some(3.into()));
+    /// assert!(Term::numeral_within(4, 9).is_err());
+    /// ```
+    pub fn numeral_within(n: u64, max_size: usize) -> Result<Self, NumeralTooLarge> {
+        let size = n.checked_mul(2).and_then(|size| size.checked_add(3));
+        match size.and_then(|size| usize::try_from(size).ok()) {
+            Some(size) if size <= max_size => Ok(numeral(0..n)),
+            _ => Err(NumeralTooLarge),
         }
-        Lam {
-            param: "f".into(),
-            rule: Lam {
-                param: "a".into(),
-                rule: out.into(),
+    }
+}
+
+impl Term {
+    /// Format the term with numerals and booleans written as literals, like `«3»` or `«true»`.
+    ///
+    /// Every subterm that's a Church numeral or boolean in normal form is folded into a literal,
+    /// and the rest is printed like `Display`. Literals don't need parens, so this is much less
+    /// noisy for arithmetic. `false` and zero are the same term; it's written `«0»`. The `«»`s
+    /// make sure a literal can't be mistaken for an ident like `3`, which also means the output
+    /// doesn't parse.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = Term::apply_all("add".into(), vec![2.into(), 3.into(), true.into()]);
+    /// assert_eq!(term.display_with_numerals(), "add «2» «3» «true»");
+    /// let term = to_term("fn x => x (fn f => fn a => f a)")?;
+    /// assert_eq!(term.display_with_numerals(), "fn x => x «1»");
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn display_with_numerals(&self) -> String {
+        self.literal().unwrap_or_else(|| match self {
+            Var(x) => x.clone(),
+            Lam { param, rule } => format!("fn {} => {}", param, rule.display_with_numerals()),
+            // the same parenthesization as `Display`, except that literals never need parens
+            Appl { left, right } => {
+                let atomic = |term: &Self| matches!(term, Var(_)) || term.literal().is_some();
+                let left_fmt = left.display_with_numerals();
+                let left_fmt = if matches!(left.as_ref(), Lam { .. }) && !atomic(left) {
+                    format!("({})", left_fmt)
+                } else {
+                    left_fmt
+                };
+                let right_fmt = right.display_with_numerals();
+                let right_fmt = if atomic(right) {
+                    right_fmt
+                } else {
+                    format!("({})", right_fmt)
+                };
+                left_fmt + " " + &right_fmt
             }
-            .into(),
+        })
+    }
+
+    /// Write the term as a literal, if it's a numeral or boolean.
+    fn literal(&self) -> Option<String> {
+        if let Ok(n) = usize::try_from(self) {
+            Some(format!("«{}»", n))
+        } else {
+            bool::try_from(self).ok().map(|b| format!("«{}»", b))
         }
     }
 }
@@ -80,39 +478,298 @@ impl From<usize> for Term {
 #[derive(Debug)]
 pub struct NotChurchNum;
 
+impl Display for NotChurchNum {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "term is not a Church numeral")
+    }
+}
+
+impl core::error::Error for NotChurchNum {}
+
+/// The requested Church numeral is over the size budget.
+#[derive(Debug)]
+pub struct NumeralTooLarge;
+
+impl Display for NumeralTooLarge {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Church numeral is too large")
+    }
+}
+
+impl core::error::Error for NumeralTooLarge {}
+
+/// Where a term stops looking like a Church numeral, as found by `Applications`.
+///
+/// Depths count the applications of `f` above the offending subterm.
+enum Malformed<'a> {
+    /// The term isn't of the form `fn f => fn a => ...` at all.
+    Header(&'a Term),
+    /// An application whose left side isn't `f`.
+    NotF {
+        f: &'a str,
+        depth: usize,
+        found: &'a Term,
+    },
+    /// Something other than `a` or an application of `f`.
+    NotBase {
+        f: &'a str,
+        a: &'a str,
+        depth: usize,
+        found: &'a Term,
+    },
+}
+
+impl Display for Malformed<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Header(found) => write!(
+                fmt,
+                "expected `fn f => fn a => ...` at the top, found {}",
+                found.describe()
+            ),
+            Self::NotF { f, depth, found } => write!(
+                fmt,
+                "expected `{}` at depth {}, found {}",
+                f,
+                depth,
+                found.describe()
+            ),
+            Self::NotBase { f, a, depth, found } => write!(
+                fmt,
+                "expected `{}` or an application of `{}` at depth {}, found {}",
+                a,
+                f,
+                depth,
+                found.describe()
+            ),
+        }
+    }
+}
+
+impl From<Malformed<'_>> for NotChurchNum {
+    fn from(_: Malformed<'_>) -> Self {
+        Self
+    }
+}
+
+/// An iterator over the `f`s in the body of a Church numeral.
+///
+/// For `fn f => fn a => f (f (... a))`, this yields `Ok(())` once per `f`, and stops when it hits
+/// the `a` at the bottom. If the body turns out to be malformed, it yields a single `Err` saying
+/// where, and then stops.
+struct Applications<'a> {
+    f: &'a str,
+    a: &'a str,
+    curr: Option<&'a Term>,
+    /// How many `f`s have been yielded so far.
+    depth: usize,
+}
+
+impl<'a> Applications<'a> {
+    fn new(term: &'a Term) -> Result<Self, Malformed<'a>> {
+        if let Lam {
+            param: f,
+            rule: box Lam { param: a, box rule },
+        } = term
+        {
+            Ok(Self {
+                f,
+                a,
+                curr: Some(rule),
+                depth: 0,
+            })
+        } else {
+            Err(Malformed::Header(term))
+        }
+    }
+
+    /// Walk the rest of the numeral, returning how many `f`s it has in total.
+    fn total(mut self) -> Result<usize, Malformed<'a>> {
+        for step in &mut self {
+            step?;
+        }
+        Ok(self.depth)
+    }
+}
+
+impl<'a> Iterator for Applications<'a> {
+    type Item = Result<(), Malformed<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // We're looking for a right-heavy binary tree of `Appl`s, where each leaf is a `Var(f)`,
+        // except for a `Var(a)` at the very bottom. Each call traverses one step down this tree,
+        // always checking the leaf on the left, and then when we stop hitting `Appl`s, we should
+        // hit `Var(a)`.
+        match self.curr.take()? {
+            Appl {
+                left: box Var(x),
+                right,
+            } if x == self.f => {
+                self.curr = Some(right);
+                // can't overflow, since each application is its own allocation
+                self.depth += 1;
+                Some(Ok(()))
+            }
+            Appl { left, .. } => Some(Err(Malformed::NotF {
+                f: self.f,
+                depth: self.depth,
+                found: left,
+            })),
+            Var(x) if x == self.a => None,
+            found => Some(Err(Malformed::NotBase {
+                f: self.f,
+                a: self.a,
+                depth: self.depth,
+                found,
+            })),
+        }
+    }
+}
+
 impl TryFrom<&Term> for usize {
     type Error = NotChurchNum;
 
+    /// Decode a Church numeral in normal form.
+    ///
+    /// This is strict: a term that only reduces to a numeral, like `succ 2`, is an error. See
+    /// `Term::as_usize` for a version that reduces first.
     fn try_from(term: &Term) -> Result<Self, Self::Error> {
-        if let Lam { param, box rule } = term {
-            let f = param; // the f in fn f => fn a => f (f (... a))
-            if let Lam { param, box rule } = rule {
-                let mut curr = rule; // the current step in the iteration
-                let a = param; // the a in the above
-
-                // We're looking for a right-heavy binary tree of `Appl`s, where each leaf is a
-                // `Var(f)`, except for a `Var(a)` at the very bottom. We're going to iteratively
-                // traverse down this tree, always checking the leaf on the left, and then when we
-                // stop hitting `Appl`s, we should hit `Var(a)`. All the while, we keep a count of
-                // the number of `f`s that we've hit.
-                let mut n = 0;
-                while let Appl { box left, right } = curr {
-                    // check that the left is a Var(f)
-                    if matches!(left, Var(x) if x == f) {
-                        n += 1;
-                        curr = right;
-                    } else {
-                        return Err(NotChurchNum);
-                    }
-                }
+        Ok(Applications::new(term)?.total()?)
+    }
+}
+
+/// How many steps `Term::as_usize` takes before giving up.
+const AS_USIZE_STEPS: usize = 10_000;
+
+impl Term {
+    /// Decode a term that computes a Church numeral.
+    ///
+    /// Like `Term::as_bool`, this reduces a clone of the term first, giving up after
+    /// `AS_USIZE_STEPS` steps, so it also recognizes terms that aren't in normal form yet.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let six = Term::factorial_program(3);
+    /// assert_eq!(six.as_usize(), Some(6));
+    /// assert!(usize::try_from(&six).is_err());
+    /// ```
+    #[must_use]
+    pub fn as_usize(&self) -> Option<usize> {
+        let normal = self.clone().reduce_bounded(AS_USIZE_STEPS).ok()?;
+        usize::try_from(&normal).ok()
+    }
+}
+
+impl Term {
+    /// Decode a Church numeral in normal form, explaining where it went wrong if it isn't one.
+    ///
+    /// This shares its traversal with `usize::try_from`, but instead of a bare `NotChurchNum`,
+    /// the error says what was expected where, counting depth in applications of `f`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// assert_eq!(Term::from(3).explain_numeral(), Ok(3));
+    /// let bad = to_term("fn f => fn a => f (f (g a))")?;
+    /// assert_eq!(
+    ///     bad.explain_numeral(),
+    ///     Err("expected `f` at depth 2, found `g`".to_string())
+    /// );
+    /// #
+    /// # Ok(())}
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a message describing the first place the term doesn't look like a numeral.
+    pub fn explain_numeral(&self) -> Result<usize, String> {
+        Applications::new(self)
+            .and_then(Applications::total)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Describe the kind of term, for error messages.
+    fn describe(&self) -> String {
+        match self {
+            Var(x) => format!("`{}`", x),
+            Lam { .. } => "a lambda".to_string(),
+            Appl { .. } => "an application".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl TryFrom<&Term> for BigUint {
+    type Error = NotChurchNum;
+
+    /// Decode a Church numeral of any size.
+    fn try_from(term: &Term) -> Result<Self, Self::Error> {
+        Applications::new(term)?
+            .try_fold(Self::default(), |n, step| step.map(|()| n + 1u8))
+            .map_err(NotChurchNum::from)
+    }
+}
+
+impl Term {
+    /// Compare two Church numerals without decoding them.
+    ///
+    /// This walks both numerals in lockstep, so it works for numerals too large to fit in a
+    /// `usize`. Returns `None` if either term isn't a Church numeral.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// # use core::cmp::Ordering;
+    /// let two: Term = 2.into();
+    /// assert_eq!(two.church_cmp(&3.into()), Some(Ordering::Less));
+    /// assert_eq!(two.church_cmp(&"x".into()), None);
+    /// ```
+    #[must_use]
+    pub fn church_cmp(&self, other: &Self) -> Option<Ordering> {
+        let mut left = Applications::new(self).ok()?;
+        let mut right = Applications::new(other).ok()?;
+        loop {
+            match (left.next(), right.next()) {
+                (Some(Ok(())), Some(Ok(()))) => (),
+                (None, None) => return Some(Ordering::Equal),
 
-                // We stopped hitting `Appl`s, so we should have a `Var(a)`.
-                if matches!(curr, Var(x) if x == a) {
-                    return Ok(n);
+                // One side ran out first, but we still have to make sure the other side is a
+                // well-formed numeral all the way down.
+                (None, Some(Ok(()))) => {
+                    return right.all(|step| step.is_ok()).then_some(Ordering::Less)
+                }
+                (Some(Ok(())), None) => {
+                    return left.all(|step| step.is_ok()).then_some(Ordering::Greater)
                 }
+
+                // At least one side is malformed.
+                _ => return None,
             }
         }
-        Err(NotChurchNum)
+    }
+
+    /// Walk a Church numeral's applications of `f` lazily, yielding once for each.
+    ///
+    /// Unlike decoding to a `usize` or `BigUint`, this only traverses as much of the term as is
+    /// asked for, so bounded checks on huge numerals are cheap. Returns `None` if the term isn't
+    /// of the form `fn f => fn a => ...`; if the body turns out to be malformed further down, the
+    /// iterator just stops there, so check with `usize::try_from` if the exact value matters.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let big: Term = 10_000.into();
+    /// assert_eq!(big.church_body_iter().unwrap().take(100).count(), 100);
+    /// assert_eq!(Term::from(3).church_body_iter().unwrap().count(), 3);
+    /// assert!(Term::from("x").church_body_iter().is_none());
+    /// ```
+    #[must_use]
+    pub fn church_body_iter(&self) -> Option<impl Iterator<Item = ()> + '_> {
+        Applications::new(self)
+            .ok()
+            .map(|apps| apps.map_while(Result::ok))
     }
 }
 
@@ -135,7 +792,7 @@ mod tests {
                 .into(),
             };
 
-            assert_eq!(got, expected);
+            assert_structural_eq!(got, expected);
         }
 
         #[test]
@@ -162,7 +819,7 @@ mod tests {
                 .into(),
             };
 
-            assert_eq!(got, expected);
+            assert_structural_eq!(got, expected);
         }
     }
 
@@ -192,6 +849,205 @@ mod tests {
             let seventeen: Term = 17.into();
             assert!(seventeen.succ().alpha_equiv(&18.into()));
         }
+
+        #[test]
+        /// Reducing, printing, and comparing a numeral this deep recursively overflows the stack.
+        fn hundred_thousand() {
+            let got = Term::from(99_999).succ().reduce(false);
+            assert!(got.is_irreducible());
+            let printed = got.to_string();
+            assert!(printed.starts_with("fn f => fn a => f (f (f "));
+            assert_eq!(printed.matches('(').count(), 99_999);
+            assert_eq!(got, Term::from(100_000));
+            assert_eq!(got.cmp(&Term::from(100_000)), Ordering::Equal);
+            assert_ne!(got, Term::from(99_999));
+        }
+    }
+
+    mod arithmetic {
+        use super::*;
+
+        #[test]
+        fn pred_zero() {
+            let zero: Term = 0.into();
+            assert!(zero.pred().alpha_equiv(&0.into()));
+        }
+
+        #[test]
+        fn pred_seventeen() {
+            let seventeen: Term = 17.into();
+            assert!(seventeen.pred().alpha_equiv(&16.into()));
+        }
+
+        #[test]
+        fn sub_truncates() {
+            let two: Term = 2.into();
+            assert!(two.saturating_sub(5.into()).alpha_equiv(&0.into()));
+        }
+
+        #[test]
+        fn is_zero() {
+            let zero: Term = 0.into();
+            let one: Term = 1.into();
+            assert!(zero.is_zero().alpha_equiv(&TRUE));
+            assert!(one.is_zero().alpha_equiv(&FALSE));
+        }
+    }
+
+    mod ops {
+        use super::*;
+
+        macro_rules! ops_tests { ($($name:ident: $left:expr, $right:expr)*) => {
+            $(
+            #[test]
+            fn $name() {
+                let (left, right): (Term, Term) = ($left.into(), $right.into());
+                let add = Term::apply_all(ADD.clone(), vec![left.clone(), right.clone()]);
+                let mul = Term::apply_all(MUL.clone(), vec![left.clone(), right.clone()]);
+                let sub = Term::apply_all(SUB.clone(), vec![left.clone(), right.clone()]);
+
+                let sum = left.clone() + right.clone();
+                assert!(sum.alpha_equiv(&add.reduce(false)));
+                assert_eq!(usize::try_from(&sum).ok(), Some($left + $right));
+
+                let product = left.clone() * right.clone();
+                assert!(product.alpha_equiv(&mul.reduce(false)));
+                assert_eq!(usize::try_from(&product).ok(), Some($left * $right));
+
+                let difference = left - right;
+                assert!(difference.alpha_equiv(&sub.reduce(false)));
+                assert_eq!(usize::try_from(&difference).ok(), Some(usize::saturating_sub($left, $right)));
+            }
+            )*
+        }}
+
+        ops_tests! {
+            zeros: 0, 0
+            zero_left: 0, 3
+            zero_right: 3, 0
+            one: 1, 4
+            two_three: 2, 3
+            five_two: 5, 2
+        }
+    }
+
+    mod div_rem {
+        use super::*;
+
+        macro_rules! div_rem_tests { ($($name:ident: $left:expr, $right:expr)*) => {
+            $(
+            #[test]
+            fn $name() {
+                let (left, right): (Term, Term) = ($left.into(), $right.into());
+                let quotient = left.clone() / right.clone();
+                let expected = usize::checked_div($left, $right).unwrap_or(0);
+                assert_eq!(usize::try_from(&quotient).ok(), Some(expected));
+
+                let remainder = left % right;
+                let expected = usize::checked_rem($left, $right).unwrap_or($left);
+                assert_eq!(usize::try_from(&remainder).ok(), Some(expected));
+            }
+            )*
+        }}
+
+        div_rem_tests! {
+            seven_two: 7, 2
+            six_three: 6, 3
+            two_five: 2, 5
+            zero_left: 0, 3
+            by_zero: 7, 0
+            by_one: 4, 1
+        }
+
+        #[test]
+        fn methods() {
+            let quotient = Term::div(7.into(), 2.into());
+            assert_eq!(usize::try_from(&quotient).ok(), Some(3));
+            let remainder = Term::rem(7.into(), 2.into());
+            assert_eq!(usize::try_from(&remainder).ok(), Some(1));
+        }
+    }
+
+    mod church_via_exp {
+        use super::*;
+
+        macro_rules! church_via_exp_tests { ($($name:ident: $base:expr, $exp:expr)*) => {
+            $(
+            #[test]
+            fn $name() {
+                let got = Term::church_via_exp($base, $exp).reduce(false);
+                assert_eq!(usize::try_from(&got).ok(), Some(usize::pow($base, $exp)));
+            }
+            )*
+        }}
+
+        church_via_exp_tests! {
+            zeroth_power: 5, 0
+            first_power: 5, 1
+            zero_base: 0, 3
+            two_ten: 2, 10
+            three_five: 3, 5
+        }
+
+        #[test]
+        fn compact() {
+            assert!(Term::church_via_exp(2, 10).size() < Term::from(1024).size() / 50);
+        }
+    }
+
+    mod church_eq {
+        use super::*;
+
+        macro_rules! church_eq_tests { ($($name:ident: $left:expr, $right:expr, $expected:expr)*) => {
+            $(
+            #[test]
+            fn $name() {
+                let left: Term = $left.into();
+                let got = left.church_eq($right.into());
+                assert_eq!(bool::try_from(&got).ok(), Some($expected));
+            }
+            )*
+        }}
+
+        church_eq_tests! {
+            three_three: 3, 3, true
+            three_four: 3, 4, false
+            four_three: 4, 3, false
+            zero_zero: 0, 0, true
+            zero_one: 0, 1, false
+        }
+    }
+
+    mod from_ints {
+        use super::*;
+
+        #[test]
+        fn agree() -> Result<(), NumeralTooLarge> {
+            for n in [0u32, 1, 17] {
+                let from_usize = Term::from(n as usize);
+                assert!(Term::try_from(u64::from(n))?.alpha_equiv(&from_usize));
+                assert!(Term::try_from(n)?.alpha_equiv(&from_usize));
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn size() -> Result<(), NumeralTooLarge> {
+            for n in [0, 1, 17] {
+                assert_eq!(
+                    Term::numeral_within(n, usize::MAX)?.size() as u64,
+                    2 * n + 3
+                );
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn too_large() {
+            assert!(Term::numeral_within(17, 36).is_err());
+            assert!(Term::numeral_within(17, 37).is_ok());
+            assert!(Term::try_from(u64::MAX).is_err());
+        }
     }
 
     mod try_into_usize {
@@ -217,6 +1073,8 @@ mod tests {
             three: 3
             seventeen: 17
             one_forty_three: 143
+            // deep enough that building, decoding, or dropping it recursively overflows the stack
+            million: 1_000_000
         }
 
         /// for more complicated terms that can't be constructed as num.into()
@@ -297,5 +1155,247 @@ mod tests {
                 rule: "x".into()
             }
         }
+
+        #[test]
+        /// The error works with `?` in functions returning a boxed error.
+        fn boxed_error() {
+            fn decode(term: &Term) -> Result<usize, Box<dyn std::error::Error>> {
+                Ok(term.try_into()?)
+            }
+            let err = decode(&"x".into()).unwrap_err();
+            assert_eq!(err.to_string(), "term is not a Church numeral");
+        }
+    }
+
+    mod display_with_numerals {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn nested() -> ParserResult<()> {
+            let term =
+                to_term("(fn m => fn n => n m) (fn f => fn a => f (f a)) (fn t => fn e => t)")?;
+            assert_eq!(
+                term.display_with_numerals(),
+                "(fn m => fn n => n m) «2» «true»"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn zero_is_false() {
+            assert_eq!(Term::from(false).display_with_numerals(), "«0»");
+        }
+
+        #[test]
+        /// A lambda that isn't a literal still gets parens on the left.
+        fn lambda_on_left() -> ParserResult<()> {
+            let term = to_term("(fn x => x) (fn f => fn a => f a) y")?;
+            assert_eq!(term.display_with_numerals(), "(fn x => x) «1» y");
+            Ok(())
+        }
+
+        #[test]
+        fn no_literals() -> ParserResult<()> {
+            let term = to_term("fn x => x (y z) (fn w => w)")?;
+            assert_eq!(term.display_with_numerals(), term.to_string());
+            Ok(())
+        }
+    }
+
+    mod as_usize {
+        use super::*;
+
+        #[test]
+        fn reduces_first() {
+            let sum = Term::apply_all(ADD.clone(), vec![2.into(), 3.into()]);
+            assert_eq!(sum.as_usize(), Some(5));
+        }
+
+        #[test]
+        fn not_a_numeral() {
+            assert_eq!(Term::from(true).as_usize(), None);
+        }
+    }
+
+    mod church_cmp {
+        use super::*;
+
+        #[test]
+        fn equal() {
+            let three: Term = 3.into();
+            assert_eq!(three.church_cmp(&3.into()), Some(Ordering::Equal));
+        }
+
+        #[test]
+        fn less() {
+            let zero: Term = 0.into();
+            assert_eq!(zero.church_cmp(&17.into()), Some(Ordering::Less));
+        }
+
+        #[test]
+        fn greater() {
+            let seventeen: Term = 17.into();
+            assert_eq!(seventeen.church_cmp(&4.into()), Some(Ordering::Greater));
+        }
+
+        #[test]
+        fn different_names() {
+            let one = Lam {
+                param: "g".into(),
+                rule: Lam {
+                    param: "b".into(),
+                    rule: Appl {
+                        left: "g".into(),
+                        right: "b".into(),
+                    }
+                    .into(),
+                }
+                .into(),
+            };
+            assert_eq!(one.church_cmp(&1.into()), Some(Ordering::Equal));
+        }
+
+        #[test]
+        fn not_numeral() {
+            let two: Term = 2.into();
+            let ident = Lam {
+                param: "x".into(),
+                rule: "x".into(),
+            };
+            assert_eq!(two.church_cmp(&ident), None);
+            assert_eq!(ident.church_cmp(&two), None);
+        }
+
+        #[test]
+        /// The longer term is only malformed past the end of the shorter one.
+        fn malformed_tail() {
+            let bad = Lam {
+                param: "f".into(),
+                rule: Lam {
+                    param: "a".into(),
+                    rule: Appl {
+                        left: "f".into(),
+                        right: Appl {
+                            left: "f".into(),
+                            right: "b".into(),
+                        }
+                        .into(),
+                    }
+                    .into(),
+                }
+                .into(),
+            };
+            let one: Term = 1.into();
+            assert_eq!(one.church_cmp(&bad), None);
+            assert_eq!(bad.church_cmp(&one), None);
+        }
+    }
+
+    mod explain_numeral {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        macro_rules! explain_numeral_errs { ($($name:ident: $input:expr, $expected:expr)*) => {
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                assert_eq!(to_term($input)?.explain_numeral(), Err($expected.to_string()));
+                Ok(())
+            }
+            )*
+        }}
+
+        #[test]
+        fn agrees_with_try_from() {
+            for n in [0, 1, 2, 17] {
+                assert_eq!(Term::from(n).explain_numeral(), Ok(n));
+            }
+        }
+
+        explain_numeral_errs! {
+            var: "x", "expected `fn f => fn a => ...` at the top, found `x`"
+            one_lambda: "fn f => f", "expected `fn f => fn a => ...` at the top, found a lambda"
+            wrong_head: "fn f => fn a => f (f ((fn x => x) a))",
+                "expected `f` at depth 2, found a lambda"
+            nested_appl: "fn f => fn a => f ((f f) a)",
+                "expected `f` at depth 1, found an application"
+            wrong_base: "fn f => fn a => f b",
+                "expected `a` or an application of `f` at depth 1, found `b`"
+            lambda_inside: "fn f => fn a => f (fn x => x)",
+                "expected `a` or an application of `f` at depth 1, found a lambda"
+        }
+    }
+
+    mod church_body_iter {
+        use super::*;
+
+        #[test]
+        fn counts() {
+            for n in [0, 1, 2, 17] {
+                let term: Term = n.into();
+                assert_eq!(term.church_body_iter().map(Iterator::count), Some(n));
+            }
+        }
+
+        #[test]
+        fn take() {
+            let big: Term = 5000.into();
+            assert_eq!(big.church_body_iter().unwrap().take(10).count(), 10);
+        }
+
+        #[test]
+        fn not_numeral() {
+            let ident = Lam {
+                param: "x".into(),
+                rule: "x".into(),
+            };
+            assert!(ident.church_body_iter().is_none());
+        }
+
+        #[test]
+        /// A malformed body ends the iteration where it goes wrong.
+        fn malformed_tail() {
+            let bad = Lam {
+                param: "f".into(),
+                rule: Lam {
+                    param: "a".into(),
+                    rule: Appl {
+                        left: "f".into(),
+                        right: "b".into(),
+                    }
+                    .into(),
+                }
+                .into(),
+            };
+            assert_eq!(bad.church_body_iter().map(Iterator::count), Some(1));
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    mod try_into_biguint {
+        use super::*;
+
+        #[test]
+        fn zero() -> Result<(), NotChurchNum> {
+            let zero: Term = 0.into();
+            let got: BigUint = (&zero).try_into()?;
+            assert_eq!(got, BigUint::from(0u8));
+            Ok(())
+        }
+
+        #[test]
+        fn one_forty_three() -> Result<(), NotChurchNum> {
+            let one_forty_three: Term = 143.into();
+            let got: BigUint = (&one_forty_three).try_into()?;
+            assert_eq!(got, BigUint::from(143u8));
+            Ok(())
+        }
+
+        #[test]
+        fn not_numeral() {
+            let got: Result<BigUint, _> = (&Term::from("x")).try_into();
+            assert!(got.is_err());
+        }
     }
 }
@@ -0,0 +1,177 @@
+//! Well-known combinators.
+use lazy_static::lazy_static;
+
+use super::{
+    bool::AND,
+    church::{ADD, SUCC},
+};
+use crate::grammar::Term;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+lazy_static! {
+    /// The identity combinator, `fn x => x`.
+    pub(crate) static ref I: Term = Term::lambda(&["x"], "x".into());
+
+    /// The constant combinator, `fn x => fn y => x`.
+    pub(crate) static ref K: Term = Term::lambda(&["x", "y"], "x".into());
+
+    /// The substitution combinator, `fn x => fn y => fn z => x z (y z)`.
+    pub(crate) static ref S: Term = Term::lambda(
+        &["x", "y", "z"],
+        Term::apply_all(
+            "x".into(),
+            vec!["z".into(), Term::apply_all("y".into(), vec!["z".into()])],
+        ),
+    );
+
+    /// The Y combinator, `fn g => (fn x => g (x x)) (fn x => g (x x))`.
+    pub(crate) static ref Y: Term = {
+        let half = Term::lambda(
+            &["x"],
+            Term::apply_all("g".into(), vec![Term::apply_all("x".into(), vec!["x".into()])]),
+        );
+        Term::lambda(&["g"], Term::apply_all(half.clone(), vec![half]))
+    };
+
+    /// The Z combinator, `fn g => (fn x => g (fn v => x x v)) (fn x => g (fn v => x x v))`.
+    ///
+    /// This is `Y` with the self-application eta-expanded, so it's a value until it's applied.
+    pub(crate) static ref Z: Term = {
+        let half = Term::lambda(
+            &["x"],
+            Term::apply_all(
+                "g".into(),
+                vec![Term::lambda(
+                    &["v"],
+                    Term::apply_all("x".into(), vec!["x".into(), "v".into()]),
+                )],
+            ),
+        );
+        Term::lambda(&["g"], Term::apply_all(half.clone(), vec![half]))
+    };
+
+    /// Well-known terms and their names, for recognizing them in the output.
+    pub(crate) static ref NAMED: Vec<(&'static str, Term)> = vec![
+        ("the identity combinator I", I.clone()),
+        ("the constant combinator K", K.clone()),
+        ("the substitution combinator S", S.clone()),
+        ("the Y combinator", Y.clone()),
+        ("the Z combinator", Z.clone()),
+        ("Church successor", SUCC.clone()),
+        ("Church addition", ADD.clone()),
+        ("boolean and", AND.clone()),
+    ];
+}
+
+impl Term {
+    /// The Y combinator, `fn g => (fn x => g (x x)) (fn x => g (x x))`.
+    ///
+    /// `Y f` reduces to `f (Y f)`, which is how recursion is written without names. It only works
+    /// under call-by-name, like our normal-order reduction: a strict evaluator reduces the
+    /// argument `Y f` before substituting it, which unfolds it forever. Use `z` for
+    /// call-by-value.
+    #[must_use]
+    pub fn y() -> Self {
+        Y.clone()
+    }
+
+    /// The Z combinator, `fn g => (fn x => g (fn v => x x v)) (fn x => g (fn v => x x v))`.
+    ///
+    /// This is the fixpoint combinator for call-by-value, which never reduces under a lambda:
+    /// `Z f` unfolds to `f (fn v => Z f v)`, and the lambda stops the unfolding until the
+    /// recursive call is actually applied. It works under normal order too, but full
+    /// applicative order (`reduce_applicative_bounded`) does reduce under lambdas, so it
+    /// unfolds `Z f` forever just like `Y f`.
+    #[must_use]
+    pub fn z() -> Self {
+        Z.clone()
+    }
+
+    /// Take the fixpoint of `f`, i.e. `Y f`.
+    ///
+    /// `f` takes the function itself as its first argument, as in `letrec`. See `y` for when this
+    /// terminates.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// // keep applying to `x` until given `false`
+    /// let go = Term::fix(to_term("fn f => fn b => b (f (fn t => fn e => e)) x")?);
+    /// let result = Term::apply_all(go, vec![true.into()]).reduce(false);
+    /// assert_eq!(result, "x".into());
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn fix(f: Self) -> Self {
+        Self::apply_all(Self::y(), vec![f])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+
+    #[test]
+    fn y() -> ParserResult<()> {
+        assert_eq!(*Y, to_term("fn g => (fn x => g (x x)) (fn x => g (x x))")?);
+        Ok(())
+    }
+
+    /// `fn f => fn n => is_zero n 1 (mul n (f (pred n)))`
+    fn factorial_step() -> ParserResult<Term> {
+        to_term(
+            "fn f => fn n => (fn n => n (fn _ => fn t => fn e => e) (fn t => fn e => t)) n
+                (fn f => fn a => f a)
+                ((fn m => fn n => fn f => m (n f)) n (f ((fn n => fn f => fn x =>
+                    n (fn g => fn h => h (g f)) (fn u => x) (fn u => u)) n)))",
+        )
+    }
+
+    #[test]
+    fn fix_factorial() -> ParserResult<()> {
+        let factorial = Term::fix(factorial_step()?);
+        let result = Term::apply_all(factorial, vec![3.into()]).reduce(false);
+        assert_eq!(usize::try_from(&result).ok(), Some(6));
+        Ok(())
+    }
+
+    #[test]
+    fn z_factorial() -> ParserResult<()> {
+        let factorial = Term::apply_all(Term::z(), vec![factorial_step()?]);
+        let result = Term::apply_all(factorial, vec![3.into()]).reduce(false);
+        assert_eq!(usize::try_from(&result).ok(), Some(6));
+        Ok(())
+    }
+
+    #[test]
+    fn z() -> ParserResult<()> {
+        let expected = to_term("fn g => (fn x => g (fn v => x x v)) (fn x => g (fn v => x x v))")?;
+        assert_eq!(Term::z(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn s_k_k_is_i() {
+        let skk = Term::apply_all(S.clone(), vec![K.clone(), K.clone()]);
+        assert!(skk.reduce(false).alpha_equiv(&I));
+    }
+
+    #[test]
+    fn named_are_closed() {
+        for (name, term) in NAMED.iter() {
+            assert!(term.free_vars().is_empty(), "{} isn't closed", name);
+        }
+    }
+
+    #[test]
+    /// The add combinator actually adds.
+    fn add() {
+        let sum = Term::apply_all(ADD.clone(), vec![2.into(), 3.into()]).reduce(false);
+        assert_eq!(usize::try_from(&sum).ok(), Some(5));
+    }
+}
@@ -0,0 +1,149 @@
+//! Church-encoded sums, i.e. tagged unions of two types.
+//!
+//! `inl x` is `fn l => fn r => l x`, and `inr y` is `fn l => fn r => r y`, so a sum is its own
+//! case analysis: applied to a function for each side, it calls the one for its side.
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{grammar::Term, reduce::get_fresh_ident};
+use Term::{Appl, Lam, Var};
+
+impl Term {
+    /// The left injection of `x`, in normal form.
+    ///
+    /// The params are renamed if they'd capture a free var of `x`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// assert_eq!(Term::inl("x".into()), to_term("fn l => fn r => l x")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn inl(x: Self) -> Self {
+        Self::injection(x, true)
+    }
+
+    /// The right injection of `y`, in normal form.
+    ///
+    /// The params are renamed if they'd capture a free var of `y`.
+    #[must_use]
+    pub fn inr(y: Self) -> Self {
+        Self::injection(y, false)
+    }
+
+    fn injection(x: Self, left: bool) -> Self {
+        let param = |name: &str| {
+            if x.occurs_free(name) {
+                get_fresh_ident(name)
+            } else {
+                name.to_string()
+            }
+        };
+        let (l, r) = (param("l"), param("r"));
+        let tag = if left { l.clone() } else { r.clone() };
+        let body = Self::apply_all(Var(tag), vec![x]);
+        Self::lambda(&[&l, &r], body)
+    }
+
+    /// Branch on a sum: `e on_left on_right`.
+    ///
+    /// This is just the application, unreduced; reducing it applies `on_left` to what's in an
+    /// `inl`, or `on_right` to what's in an `inr`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let (yes, no) = (to_term("fn _ => fn t => fn e => t")?, to_term("fn _ => fn t => fn e => e")?);
+    /// let is_left = |e| Term::case(e, yes.clone(), no.clone()).reduce(false);
+    /// assert_eq!(bool::try_from(&is_left(Term::inl("x".into()))).ok(), Some(true));
+    /// assert_eq!(bool::try_from(&is_left(Term::inr("y".into()))).ok(), Some(false));
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn case(e: Self, on_left: Self, on_right: Self) -> Self {
+        Self::apply_all(e, vec![on_left, on_right])
+    }
+
+    /// Decode a sum in normal form.
+    ///
+    /// Returns `None` if this isn't a sum, `Some(Ok(x))` for `inl x`, and `Some(Err(y))` for
+    /// `inr y`.
+    #[must_use]
+    pub fn to_either(&self) -> Option<Result<Self, Self>> {
+        let Lam {
+            param: l,
+            rule: box Lam { param: r, rule },
+        } = self
+        else {
+            return None;
+        };
+        if l == r {
+            return None;
+        }
+        match rule.as_ref() {
+            Appl {
+                left: box Var(x),
+                right: box inner,
+            } if !inner.occurs_free(l) && !inner.occurs_free(r) => {
+                if x == l {
+                    Some(Ok(inner.clone()))
+                } else if x == r {
+                    Some(Err(inner.clone()))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammar::Term, to_term, ParserResult};
+
+    #[test]
+    fn inl() {
+        let three = Term::inl(3.into()).to_either().and_then(Result::ok);
+        assert_eq!(three.and_then(|t| usize::try_from(&t).ok()), Some(3));
+    }
+
+    #[test]
+    fn inr() {
+        assert_eq!(Term::inr("y".into()).to_either(), Some(Err("y".into())));
+    }
+
+    #[test]
+    fn avoids_capture() {
+        assert_eq!(Term::inl("r".into()).to_either(), Some(Ok("r".into())));
+        assert_eq!(Term::inr("l".into()).to_either(), Some(Err("l".into())));
+    }
+
+    #[test]
+    /// Case analysis applies the function for the right side.
+    fn case() -> ParserResult<()> {
+        let on_left = to_term("fn x => f x")?;
+        let on_right = to_term("fn y => g y")?;
+        let left = Term::case(Term::inl("a".into()), on_left.clone(), on_right.clone());
+        assert_eq!(left.reduce(false), to_term("f a")?);
+        let right = Term::case(Term::inr("b".into()), on_left, on_right);
+        assert_eq!(right.reduce(false), to_term("g b")?);
+        Ok(())
+    }
+
+    #[test]
+    fn not_an_either() -> ParserResult<()> {
+        assert_eq!(to_term("fn l => fn r => l")?.to_either(), None);
+        assert_eq!(to_term("fn l => fn r => l r")?.to_either(), None);
+        assert_eq!(to_term("fn l => fn r => x y")?.to_either(), None);
+        assert_eq!(to_term("fn l => fn l => l y")?.to_either(), None);
+        assert_eq!(to_term("x")?.to_either(), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+//! Church lists.
+//!
+//! A list is its own right fold: `[x, y]` is `fn c => fn n => c x (c y n)`.
+use lazy_static::lazy_static;
+
+use super::church::SUCC;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{grammar::Term, reduce::get_fresh_ident};
+use Term::{Appl, Lam, Var};
+
+lazy_static! {
+    /// `fn c => fn n => n`
+    pub(crate) static ref NIL: Term = Term::lambda(&["c", "n"], "n".into());
+
+    /// `fn h => fn t => fn c => fn n => c h (t c n)`
+    pub(crate) static ref CONS: Term = Term::lambda(
+        &["h", "t", "c", "n"],
+        Term::apply_all(
+            "c".into(),
+            vec![
+                "h".into(),
+                Term::apply_all("t".into(), vec!["c".into(), "n".into()])
+            ]
+        )
+    );
+
+    /// `fn xs => fn ys => fn c => fn n => xs c (ys c n)`
+    static ref APPEND: Term = Term::lambda(
+        &["xs", "ys", "c", "n"],
+        Term::apply_all(
+            "xs".into(),
+            vec![
+                "c".into(),
+                Term::apply_all("ys".into(), vec!["c".into(), "n".into()])
+            ]
+        )
+    );
+
+    /// `fn f => fn xs => fn c => fn n => xs (fn h => fn t => c (f h) t) n`
+    static ref MAP: Term = Term::lambda(
+        &["f", "xs", "c", "n"],
+        Term::apply_all(
+            "xs".into(),
+            vec![
+                Term::lambda(
+                    &["h", "t"],
+                    Term::apply_all(
+                        "c".into(),
+                        vec![Term::apply_all("f".into(), vec!["h".into()]), "t".into()]
+                    )
+                ),
+                "n".into()
+            ]
+        )
+    );
+
+    /// `fn xs => xs (fn _ => fn acc => succ acc) 0`
+    static ref LENGTH: Term = Term::lambda(
+        &["xs"],
+        Term::apply_all(
+            "xs".into(),
+            vec![
+                Term::lambda(
+                    &["_", "acc"],
+                    Term::apply_all(SUCC.clone(), vec!["acc".into()])
+                ),
+                0.into()
+            ]
+        )
+    );
+}
+
+impl Term {
+    /// Encode a list of terms, in normal form.
+    ///
+    /// The list's params are renamed if they'd capture a free var of any element.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let list = Term::from_list(vec!["x".into(), "y".into()]);
+    /// assert_eq!(list, to_term("fn c => fn n => c x (c y n)")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn from_list(items: Vec<Self>) -> Self {
+        let param = |name: &str| {
+            if items.iter().any(|item| item.occurs_free(name)) {
+                get_fresh_ident(name)
+            } else {
+                name.to_string()
+            }
+        };
+        let (c, n) = (param("c"), param("n"));
+        let body = items.into_iter().rev().fold(Var(n.clone()), |tail, item| {
+            Self::apply_all(Var(c.clone()), vec![item, tail])
+        });
+        Self::lambda(&[&c, &n], body)
+    }
+
+    /// Concatenate two lists, reduced to normal form.
+    ///
+    /// A list is its own right fold, so this folds `cons` over `self`, starting from `other`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let xs = Term::from_list(vec!["x".into()]);
+    /// let ys = Term::from_list(vec!["y".into(), "z".into()]);
+    /// assert_eq!(
+    ///     xs.list_append(ys).to_list(),
+    ///     Some(vec!["x".into(), "y".into(), "z".into()])
+    /// );
+    /// ```
+    #[must_use]
+    pub fn list_append(self, other: Self) -> Self {
+        Self::apply_all(APPEND.clone(), vec![self, other]).reduce(false)
+    }
+
+    /// Apply `f` to every item of a list, reduced to normal form.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let list = Term::from_list(vec![1.into(), 2.into()]);
+    /// let succ = to_term("fn n => fn f => fn a => f (n f a)")?;
+    /// assert_eq!(list.list_map(succ).to_list(), Some(vec![2.into(), 3.into()]));
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn list_map(self, f: Self) -> Self {
+        Self::apply_all(MAP.clone(), vec![f, self]).reduce(false)
+    }
+
+    /// Count the items of a list, giving a Church numeral in normal form.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let list = Term::from_list(vec!["x".into(), "y".into()]);
+    /// assert_eq!(usize::try_from(&list.list_length()).ok(), Some(2));
+    /// ```
+    #[must_use]
+    pub fn list_length(self) -> Self {
+        Self::apply_all(LENGTH.clone(), vec![self]).reduce(false)
+    }
+
+    /// Decode a list in normal form, or return `None` if this isn't one.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let list = to_term("fn c => fn n => c x (c y n)")?;
+    /// assert_eq!(list.to_list(), Some(vec!["x".into(), "y".into()]));
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn to_list(&self) -> Option<Vec<Self>> {
+        let Lam {
+            param: c,
+            rule: box Lam { param: n, rule },
+        } = self
+        else {
+            return None;
+        };
+        if c == n {
+            return None;
+        }
+
+        let mut items = vec![];
+        let mut curr = rule.as_ref();
+        loop {
+            match curr {
+                Var(x) if x == n => return Some(items),
+                Appl {
+                    left:
+                        box Appl {
+                            left: box Var(x),
+                            right: box item,
+                        },
+                    right: box tail,
+                } if x == c && !item.occurs_free(c) && !item.occurs_free(n) => {
+                    items.push(item.clone());
+                    curr = tail;
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+
+    #[test]
+    fn nil() {
+        assert_eq!(NIL.to_list(), Some(vec![]));
+        assert_eq!(Term::from_list(vec![]), *NIL);
+    }
+
+    #[test]
+    fn round_trip() {
+        let items: Vec<Term> = vec![1.into(), "x".into(), NIL.clone()];
+        assert_eq!(Term::from_list(items.clone()).to_list(), Some(items));
+    }
+
+    #[test]
+    /// Consing onto a list reduces to the same thing `from_list` builds.
+    fn cons() {
+        let list = Term::apply_all(
+            CONS.clone(),
+            vec![1.into(), Term::from_list(vec![2.into()])],
+        );
+        assert!(list
+            .reduce(false)
+            .alpha_equiv(&Term::from_list(vec![1.into(), 2.into()])));
+    }
+
+    #[test]
+    fn append() {
+        let xs = Term::from_list(vec![1.into(), 2.into()]);
+        let ys = Term::from_list(vec![3.into(), 4.into()]);
+        assert_eq!(
+            xs.list_append(ys).to_list(),
+            Some(vec![1.into(), 2.into(), 3.into(), 4.into()])
+        );
+    }
+
+    #[test]
+    fn append_nil() {
+        let xs = Term::from_list(vec!["x".into()]);
+        assert_eq!(NIL.clone().list_append(xs.clone()), xs);
+        assert_eq!(xs.clone().list_append(NIL.clone()), xs);
+    }
+
+    #[test]
+    fn map() {
+        let list = Term::from_list(vec![1.into(), 2.into(), 3.into()]);
+        assert_eq!(
+            list.list_map(SUCC.clone()).to_list(),
+            Some(vec![2.into(), 3.into(), 4.into()])
+        );
+        assert_eq!(NIL.clone().list_map(SUCC.clone()), *NIL);
+    }
+
+    #[test]
+    fn length() {
+        let list = Term::from_list(vec!["a".into(), "b".into(), "c".into()]);
+        assert_eq!(usize::try_from(&list.list_length()).ok(), Some(3));
+        assert_eq!(usize::try_from(&NIL.clone().list_length()).ok(), Some(0));
+    }
+
+    #[test]
+    /// The list's params are renamed if they'd capture a free var of `f`.
+    fn map_avoids_capture() {
+        let list = Term::from_list(vec!["x".into()]);
+        let pair_with_c = Term::lambda(&["y"], Term::apply_all("y".into(), vec!["c".into()]));
+        assert_eq!(
+            list.list_map(pair_with_c).to_list(),
+            Some(vec![Term::apply_all("x".into(), vec!["c".into()])])
+        );
+    }
+
+    #[test]
+    fn avoids_capture() {
+        let list = Term::from_list(vec!["c".into(), "n".into()]);
+        assert_eq!(list.to_list(), Some(vec!["c".into(), "n".into()]));
+    }
+
+    #[test]
+    fn not_a_list() -> ParserResult<()> {
+        assert_eq!(to_term("fn c => fn n => c x")?.to_list(), None);
+        assert_eq!(to_term("fn c => fn n => c x (c (c y) n)")?.to_list(), None);
+        assert_eq!(to_term("fn c => fn c => c")?.to_list(), None);
+        assert_eq!(to_term("x")?.to_list(), None);
+        Ok(())
+    }
+}
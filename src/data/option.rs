@@ -0,0 +1,118 @@
+//! Church-encoded optionals.
+//!
+//! `none` is `fn n => fn j => n`, and `some x` is `fn n => fn j => j x`.
+use lazy_static::lazy_static;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{grammar::Term, reduce::get_fresh_ident};
+use Term::{Appl, Lam, Var};
+
+lazy_static! {
+    /// `fn n => fn j => n`
+    static ref NONE: Term = Term::lambda(&["n", "j"], "n".into());
+}
+
+impl Term {
+    /// The empty optional.
+    #[must_use]
+    pub fn none() -> Self {
+        NONE.clone()
+    }
+
+    /// The optional containing `x`, in normal form.
+    ///
+    /// The params are renamed if they'd capture a free var of `x`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// assert_eq!(Term::some("x".into()), to_term("fn n => fn j => j x")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn some(x: Self) -> Self {
+        let param = |name: &str| {
+            if x.occurs_free(name) {
+                get_fresh_ident(name)
+            } else {
+                name.to_string()
+            }
+        };
+        let (n, j) = (param("n"), param("j"));
+        let body = Self::apply_all(Var(j.clone()), vec![x]);
+        Self::lambda(&[&n, &j], body)
+    }
+
+    /// Decode an optional in normal form.
+    ///
+    /// Returns `None` if this isn't an optional, `Some(None)` for `none`, and `Some(Some(x))` for
+    /// `some x`.
+    #[must_use]
+    pub fn to_option(&self) -> Option<Option<Self>> {
+        let Lam {
+            param: n,
+            rule: box Lam { param: j, rule },
+        } = self
+        else {
+            return None;
+        };
+        if n == j {
+            return None;
+        }
+        match rule.as_ref() {
+            Var(x) if x == n => Some(None),
+            Appl {
+                left: box Var(x),
+                right: box inner,
+            } if x == j && !inner.occurs_free(n) && !inner.occurs_free(j) => {
+                Some(Some(inner.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammar::Term, to_term, ParserResult};
+
+    #[test]
+    fn none() {
+        assert_eq!(Term::none().to_option(), Some(None));
+    }
+
+    #[test]
+    fn some() {
+        let three = Term::some(3.into()).to_option().flatten();
+        assert_eq!(three.and_then(|t| usize::try_from(&t).ok()), Some(3));
+    }
+
+    #[test]
+    fn avoids_capture() {
+        assert_eq!(Term::some("j".into()).to_option(), Some(Some("j".into())));
+    }
+
+    #[test]
+    /// Case analysis on an optional picks the right branch.
+    fn reduced() -> ParserResult<()> {
+        let default = to_term("fn m => m 0 (fn x => x)")?;
+        let zero = Term::apply_all(default.clone(), vec![Term::none()]).reduce(false);
+        assert_eq!(zero, "0".into());
+        let x = Term::apply_all(default, vec![Term::some("y".into())]).reduce(false);
+        assert_eq!(x, "y".into());
+        Ok(())
+    }
+
+    #[test]
+    fn not_an_option() -> ParserResult<()> {
+        assert_eq!(to_term("fn n => fn j => j")?.to_option(), None);
+        assert_eq!(to_term("fn n => fn j => j n")?.to_option(), None);
+        assert_eq!(to_term("fn n => fn n => n")?.to_option(), None);
+        assert_eq!(to_term("x")?.to_option(), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! Reference programs, built out of the other encodings.
+//!
+//! These are the kinds of programs you'd otherwise write by hand in a `.m3lc` file, like
+//! `examples/fibrec.m3lc`. They're recursive through the Y combinator, so they only terminate
+//! under normal-order reduction.
+use lazy_static::lazy_static;
+
+use super::{
+    church::{ADD, IS_ZERO, MUL, PRED},
+    combinator::Y,
+};
+use crate::grammar::Term;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+lazy_static! {
+    /// `fn a => fn b => fn s => s a b`
+    static ref PAIR: Term = Term::lambda(
+        &["a", "b", "s"],
+        Term::apply_all("s".into(), vec!["a".into(), "b".into()])
+    );
+
+    /// `fn p => p (fn t => fn e => t)`
+    static ref FST: Term = Term::lambda(
+        &["p"],
+        Term::apply_all("p".into(), vec![true.into()])
+    );
+
+    /// `fn p => p (fn t => fn e => e)`
+    static ref SND: Term = Term::lambda(
+        &["p"],
+        Term::apply_all("p".into(), vec![false.into()])
+    );
+
+    /// `y (fn f => fn n => is_zero n 1 (mul n (f (pred n))))`
+    static ref FACTORIAL: Term = Term::apply_all(
+        Y.clone(),
+        vec![Term::lambda(
+            &["f", "n"],
+            Term::apply_all(
+                IS_ZERO.clone(),
+                vec![
+                    "n".into(),
+                    1.into(),
+                    Term::apply_all(
+                        MUL.clone(),
+                        vec![
+                            "n".into(),
+                            Term::apply_all(
+                                "f".into(),
+                                vec![Term::apply_all(PRED.clone(), vec!["n".into()])]
+                            ),
+                        ]
+                    ),
+                ]
+            )
+        )]
+    );
+
+    /// `fn n => fst (y step n (pair 0 1))`, where `step` steps the pair `(fib k, fib (k + 1))`
+    /// `n` times:
+    ///
+    /// `fn f => fn n => fn p => is_zero n p (f (pred n) (pair (snd p) (add (fst p) (snd p))))`
+    static ref FIBONACCI: Term = {
+        let fst = |p: &str| Term::apply_all(FST.clone(), vec![p.into()]);
+        let snd = |p: &str| Term::apply_all(SND.clone(), vec![p.into()]);
+        let next = Term::apply_all(
+            PAIR.clone(),
+            vec![snd("p"), Term::apply_all(ADD.clone(), vec![fst("p"), snd("p")])],
+        );
+        let step = Term::lambda(
+            &["f", "n", "p"],
+            Term::apply_all(
+                IS_ZERO.clone(),
+                vec![
+                    "n".into(),
+                    "p".into(),
+                    Term::apply_all(
+                        "f".into(),
+                        vec![Term::apply_all(PRED.clone(), vec!["n".into()]), next],
+                    ),
+                ],
+            ),
+        );
+        let start = Term::apply_all(PAIR.clone(), vec![0.into(), 1.into()]);
+        Term::lambda(
+            &["n"],
+            Term::apply_all(
+                FST.clone(),
+                vec![Term::apply_all(Y.clone(), vec![step, "n".into(), start])],
+            ),
+        )
+    };
+}
+
+impl Term {
+    /// Build a program that computes `n!` as a Church numeral.
+    ///
+    /// The program is the usual recursive definition, `fact n = if n == 0 then 1 else n * fact (n -
+    /// 1)`, tied together with the Y combinator and applied to the numeral `n`. It isn't reduced.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let fact = Term::factorial_program(3).reduce(false);
+    /// assert_eq!(usize::try_from(&fact).ok(), Some(6));
+    /// ```
+    #[must_use]
+    pub fn factorial_program(n: usize) -> Self {
+        Self::apply_all(FACTORIAL.clone(), vec![n.into()])
+    }
+
+    /// Build a program that computes the `n`th Fibonacci number as a Church numeral.
+    ///
+    /// The program recurses `n` times through the Y combinator, carrying a Church pair of
+    /// consecutive Fibonacci numbers, starting from `(0, 1)`. It isn't reduced.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::Term;
+    /// let fib = Term::fibonacci_program(5).reduce(false);
+    /// assert_eq!(usize::try_from(&fib).ok(), Some(5));
+    /// ```
+    #[must_use]
+    pub fn fibonacci_program(n: usize) -> Self {
+        Self::apply_all(FIBONACCI.clone(), vec![n.into()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! program_tests { ($($name:ident: $program:ident, $n:expr, $expected:expr)*) => {
+        mod programs {
+            use super::*;
+            $(
+            #[test]
+            fn $name() {
+                let out = Term::$program($n).reduce(false);
+                assert_eq!(usize::try_from(&out).ok(), Some($expected));
+            }
+            )*
+        }
+    }}
+
+    program_tests! {
+        factorial_zero: factorial_program, 0, 1
+        factorial_one: factorial_program, 1, 1
+        factorial_three: factorial_program, 3, 6
+        fibonacci_zero: fibonacci_program, 0, 0
+        fibonacci_one: fibonacci_program, 1, 1
+        fibonacci_two: fibonacci_program, 2, 1
+        fibonacci_five: fibonacci_program, 5, 5
+    }
+
+    #[test]
+    fn closed() {
+        for term in [&*PAIR, &*FST, &*SND, &*FACTORIAL, &*FIBONACCI] {
+            assert!(term.is_closed(), "{} isn't closed", term);
+        }
+    }
+}
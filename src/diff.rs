@@ -0,0 +1,177 @@
+//! Find where two terms differ.
+use core::fmt::Display;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{grammar::Term, path::Direction};
+
+/// The first place two terms differ, up to alpha-equivalence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermDiff<'a> {
+    /// The path from the root of both terms to the differing subterms.
+    pub path: Vec<Direction>,
+    /// The subterm of the first term.
+    pub left: &'a Term,
+    /// The subterm of the second term.
+    pub right: &'a Term,
+}
+
+impl Display for TermDiff<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "terms differ at {:?}: `{}` vs `{}`",
+            self.path, self.left, self.right
+        )
+    }
+}
+
+impl Term {
+    /// Find the first place `self` and `other` differ, up to alpha-equivalence.
+    ///
+    /// Both terms are walked in lockstep, left to right, and the first pair of subterms that
+    /// can't be alpha-equivalent is returned, along with the path to them. Returns `None` exactly
+    /// when `self.alpha_equiv(other)`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Direction};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let left = to_term("fn x => x y")?;
+    /// let right = to_term("fn z => z w")?;
+    /// let diff = left.diff(&right).expect("y and w differ");
+    /// assert_eq!(diff.path, vec![Direction::LamBody, Direction::ApplRight]);
+    /// assert_eq!(diff.left, &"y".into());
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Option<TermDiff<'a>> {
+        self.diff_impl(other, &mut vec![], &mut vec![])
+    }
+
+    fn diff_impl<'a>(
+        &'a self,
+        other: &'a Self,
+        ctx: &mut Vec<(&'a str, &'a str)>,
+        path: &mut Vec<Direction>,
+    ) -> Option<TermDiff<'a>> {
+        // This is `alpha_equiv_impl`, except we keep track of where we are so we can report it.
+        let equiv = match (self, other) {
+            (Self::Var(x), Self::Var(y)) => ctx
+                .iter()
+                .rfind(|(a, b)| a == x || b == y)
+                .map_or(x == y, |(a, b)| a == x && b == y),
+
+            (
+                Self::Lam {
+                    param: param1,
+                    rule: rule1,
+                },
+                Self::Lam {
+                    param: param2,
+                    rule: rule2,
+                },
+            ) => {
+                ctx.push((param1, param2));
+                path.push(Direction::LamBody);
+                let out = rule1.diff_impl(rule2, ctx, path);
+                path.pop();
+                ctx.pop();
+                return out;
+            }
+
+            (
+                Self::Appl {
+                    left: left1,
+                    right: right1,
+                },
+                Self::Appl {
+                    left: left2,
+                    right: right2,
+                },
+            ) => {
+                path.push(Direction::ApplLeft);
+                let out = left1.diff_impl(left2, ctx, path).or_else(|| {
+                    *path.last_mut().expect("we just pushed") = Direction::ApplRight;
+                    right1.diff_impl(right2, ctx, path)
+                });
+                path.pop();
+                return out;
+            }
+
+            _ => false,
+        };
+
+        (!equiv).then(|| TermDiff {
+            path: path.clone(),
+            left: self,
+            right: other,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+    use Direction::{ApplLeft, ApplRight, LamBody};
+
+    #[test]
+    fn alpha_equivalent() -> ParserResult<()> {
+        let left = to_term("fn f => fn a => f (f a)")?;
+        let right = to_term("fn g => fn b => g (g b)")?;
+        assert_eq!(left.diff(&right), None);
+        Ok(())
+    }
+
+    #[test]
+    fn root() -> ParserResult<()> {
+        let left = to_term("fn x => x")?;
+        let right = to_term("x y")?;
+        let diff = left.diff(&right).expect("terms differ");
+        assert!(diff.path.is_empty());
+        assert_eq!((diff.left, diff.right), (&left, &right));
+        Ok(())
+    }
+
+    #[test]
+    /// Bound and free variables with the same name still differ.
+    fn bound_vs_free() -> ParserResult<()> {
+        let left = to_term("fn x => fn y => x")?;
+        let right = to_term("fn y => fn y => x")?;
+        let diff = left.diff(&right).expect("terms differ");
+        assert_eq!(diff.path, vec![LamBody, LamBody]);
+        assert_eq!((diff.left, diff.right), (&"x".into(), &"x".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn first_difference_wins() -> ParserResult<()> {
+        let left = to_term("a (b c) d")?;
+        let right = to_term("a (b e) f")?;
+        let diff = left.diff(&right).expect("terms differ");
+        assert_eq!(diff.path, vec![ApplLeft, ApplRight, ApplRight]);
+        assert_eq!((diff.left, diff.right), (&"c".into(), &"e".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn agrees_with_alpha_equiv() -> ParserResult<()> {
+        let terms = [
+            "fn x => x",
+            "fn y => y",
+            "fn x => y",
+            "x (fn y => y)",
+            "(fn x => x) y",
+        ];
+        for left in terms {
+            for right in terms {
+                let (left, right) = (to_term(left)?, to_term(right)?);
+                assert_eq!(left.diff(&right).is_none(), left.alpha_equiv(&right));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,24 @@
 //! The abstract grammar.
-use std::fmt::Display;
+use alloc::collections::BTreeMap;
+use core::{cmp::Ordering, fmt::Display};
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{data::combinator::Y, reduce::get_fresh_ident, visit::Visitor, Map, Set};
 
 /// A single lambda term.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// `==` is alpha-equivalence, so `fn x => x` and `fn y => y` are equal; use `structural_eq` if
+/// the names matter too.
+///
+/// Terms are totally ordered, in a way that agrees with `==`: `Var`s come before `Lam`s, which
+/// come before `Appl`s, and terms of the same variant are ordered lexicographically by their
+/// subterms. Params don't count, bound vars come before free vars, bound vars are ordered by how
+/// far out their binders are, and free vars are ordered by name. This is good for sorting
+/// deterministically and putting terms in a `BTreeSet`, which then dedupes alpha-equivalent
+/// terms.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Term {
     // Many things here are heap-allocated. You obviously have to box the recursive types so the
     // compiler can size the type, but it makes for awkward code (lots of `into`s to coerce to
@@ -26,6 +42,214 @@ pub enum Term {
     Appl { left: Box<Term>, right: Box<Term> },
 }
 
+impl Drop for Term {
+    /// Drop the term without recursing, so deep terms like big numerals don't overflow the stack.
+    ///
+    /// The derived drop would recurse once per level. Instead, we walk down the term, moving each
+    /// subterm's children out before it's dropped, so it has none left to recurse into. Only an
+    /// appl with two non-var sides needs the stack, so usually this doesn't allocate.
+    fn drop(&mut self) {
+        let mut stack = vec![];
+        let mut next = self.take_children(&mut stack);
+        while let Some(mut term) = next.or_else(|| stack.pop()) {
+            next = term.take_children(&mut stack);
+        }
+    }
+}
+
+impl Term {
+    /// Move the term's non-var children out, leaving empty vars, which don't allocate, behind.
+    ///
+    /// Returns one of them, and pushes the other, if there is one, onto `stack`.
+    fn take_children(&mut self, stack: &mut Vec<Self>) -> Option<Self> {
+        let take = |child: &mut Self| {
+            (!matches!(child, Self::Var(_)))
+                .then(|| core::mem::replace(child, Self::Var(String::new())))
+        };
+        match self {
+            Self::Var(_) => None,
+            Self::Lam { rule, .. } => take(rule),
+            Self::Appl { left, right } => match (take(left), take(right)) {
+                (Some(left), Some(right)) => {
+                    stack.push(right);
+                    Some(left)
+                }
+                (left, right) => left.or(right),
+            },
+        }
+    }
+}
+
+impl Clone for Term {
+    /// Clone the term, without recursing down chains of appls' right sides, like `Drop`.
+    fn clone(&self) -> Self {
+        match self {
+            Self::Var(x) => Self::Var(x.clone()),
+            Self::Lam { param, rule } => Self::Lam {
+                param: param.clone(),
+                rule: rule.clone(),
+            },
+            Self::Appl { left, right } if !matches!(**right, Self::Appl { .. }) => Self::Appl {
+                left: left.clone(),
+                right: right.clone(),
+            },
+            Self::Appl { .. } => {
+                // clone the left sides on the way down the chain, then put the appls back
+                // together from the bottom up
+                let mut lefts = vec![];
+                let mut term = self;
+                while let Self::Appl { left, right } = term {
+                    lefts.push(left.clone());
+                    term = right;
+                }
+                lefts
+                    .into_iter()
+                    .rev()
+                    .fold(term.clone(), |right, left| Self::Appl {
+                        left,
+                        right: right.into(),
+                    })
+            }
+        }
+    }
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Self) -> bool {
+        self.alpha_equiv(other)
+    }
+}
+
+impl Eq for Term {}
+
+impl PartialOrd for Term {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Term {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_impl(other)
+    }
+}
+
+impl Term {
+    /// Check whether two terms are exactly the same, including the names of bound variables.
+    ///
+    /// This is stricter than `==`, which is alpha-equivalence.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let (x, y) = (to_term("fn x => x")?, to_term("fn y => y")?);
+    /// assert_eq!(x, y);
+    /// assert!(!x.structural_eq(&y));
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Var(x), Self::Var(y)) => x == y,
+            (
+                Self::Lam {
+                    param: param1,
+                    rule: rule1,
+                },
+                Self::Lam {
+                    param: param2,
+                    rule: rule2,
+                },
+            ) => param1 == param2 && rule1.structural_eq(rule2),
+            (
+                Self::Appl {
+                    left: left1,
+                    right: right1,
+                },
+                Self::Appl {
+                    left: left2,
+                    right: right2,
+                },
+            ) => left1.structural_eq(left2) && right1.structural_eq(right2),
+            _ => false,
+        }
+    }
+
+    /// Compare up to alpha-equivalence.
+    fn cmp_impl(&self, other: &Self) -> Ordering {
+        // the variants, in order
+        let rank = |term: &Self| match term {
+            Self::Var(_) => 0,
+            Self::Lam { .. } => 1,
+            Self::Appl { .. } => 2,
+        };
+
+        // Like `alpha_equiv_impl`, this keeps a stack of pairs of subterms still to compare,
+        // rather than recursing, along with how many params were bound on each side where they
+        // are. Left sides are compared first, so this is lexicographic.
+        let (mut bound1, mut bound2) = (vec![], vec![]);
+        let mut pending = vec![(self, other, 0)];
+        while let Some((term1, term2, depth)) = pending.pop() {
+            bound1.truncate(depth);
+            bound2.truncate(depth);
+            let ordering = match (term1, term2) {
+                // Bound vars are compared by the index of their binder, like de Bruijn levels,
+                // and come before free vars. `None < Some`, so flip the options to get that.
+                (Self::Var(x), Self::Var(y)) => {
+                    let level =
+                        |bound: &[&str], x: &str| bound.iter().rposition(|param| *param == x);
+                    match (level(&bound1, x), level(&bound2, y)) {
+                        (Some(i), Some(j)) => i.cmp(&j),
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (None, None) => x.cmp(y),
+                    }
+                }
+
+                (
+                    Self::Lam {
+                        param: param1,
+                        rule: rule1,
+                    },
+                    Self::Lam {
+                        param: param2,
+                        rule: rule2,
+                    },
+                ) => {
+                    bound1.push(param1.as_str());
+                    bound2.push(param2.as_str());
+                    pending.push((rule1, rule2, depth + 1));
+                    Ordering::Equal
+                }
+
+                (
+                    Self::Appl {
+                        left: left1,
+                        right: right1,
+                    },
+                    Self::Appl {
+                        left: left2,
+                        right: right2,
+                    },
+                ) => {
+                    pending.push((right1, right2, depth));
+                    pending.push((left1, left2, depth));
+                    Ordering::Equal
+                }
+
+                _ => rank(term1).cmp(&rank(term2)),
+            };
+            if ordering.is_ne() {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
 // Importantly, this impl converts a string into a `Term::Var`, it does _not_ try to parse the string
 // as a lambda. This would be fallible behavior, which is not ok for `From`.
 impl From<String> for Term {
@@ -54,60 +278,484 @@ impl From<&str> for Box<Term> {
     }
 }
 
-impl Display for Term {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let message = match self {
-            Self::Var(s) => s.to_string(),
-            Self::Lam { param, rule } => format!("fn {} => {}", param, rule),
-
-            // We need special handling here to deal with parenthesization. I _think_ that this
-            // parenthesization is invertible, i.e. that we don't drop any associativity
-            // information and so `to_term(t.to_string())` always produces the original term.
-            // But I haven't verified this formally or anything. My informal analysis is explained
-            // in the comments below.
-            Self::Appl {
-                box left,
-                box right,
-            } => {
-                let left_fmt = if let Self::Lam { .. } = left {
-                    // parenthesize lambdas on the left: consider `(fn x => x) g` vs `fn x => x g`
-                    format!("({})", left)
-                } else {
-                    // no need to parenthesize vars, ever
-                    //
-                    // no need to parenthesize left-heavy appls because of associativity
-                    left.to_string()
+impl Term {
+    /// Apply `head` to each of `args` in turn.
+    ///
+    /// Application is left-associative, so this builds the left-heavy spine `head a1 a2 ... an`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = Term::apply_all("f".into(), vec!["a".into(), "b".into(), "c".into()]);
+    /// assert_eq!(term, to_term("f a b c")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn apply_all(head: Self, args: Vec<Self>) -> Self {
+        args.into_iter().fold(head, |left, right| Self::Appl {
+            left: left.into(),
+            right: right.into(),
+        })
+    }
+
+    /// Abstract `body` over each of `params` in turn.
+    ///
+    /// The first param is the outermost, so this builds `fn p1 => fn p2 => ... => body`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = Term::lambda(&["f", "a"], Term::apply_all("f".into(), vec!["a".into()]));
+    /// assert_eq!(term, to_term("fn f => fn a => f a")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn lambda(params: &[&str], body: Self) -> Self {
+        params.iter().rev().fold(body, |rule, param| Self::Lam {
+            param: (*param).to_string(),
+            rule: rule.into(),
+        })
+    }
+
+    /// Decompose an application into its head and arguments.
+    ///
+    /// This is the inverse of `apply_all`: for `head a1 a2 ... an`, returns
+    /// `(head, [a1, ..., an])`. A term that isn't an `Appl` is its own head, with no arguments.
+    #[must_use]
+    pub fn spine(&self) -> (&Self, Vec<&Self>) {
+        let mut head = self;
+        let mut args = vec![];
+        while let Self::Appl { left, right } = head {
+            args.push(right.as_ref());
+            head = left;
+        }
+        // we walked down from the outermost application, i.e. the last argument
+        args.reverse();
+        (head, args)
+    }
+
+    /// Check that no var or param name contains a `.`.
+    ///
+    /// Fresh names from `get_fresh_ident` are the only names with a `.` in them, which is what
+    /// makes them fresh: the parser won't accept one, but a term built by hand can have one
+    /// anyway. If it matches a fresh name that reduction makes later, substitution can capture
+    /// it, and the result is wrong. Reduction makes names like this itself, so only validate
+    /// terms before reducing them.
+    ///
+    /// # Errors
+    /// Returns a message naming the first bad name found.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// assert!(to_term("fn x => x y")?.validate().is_ok());
+    /// assert!(Term::lambda(&["x.5"], "x.5".into()).validate().is_err());
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn validate(&self) -> Result<(), String> {
+        let check = |name: &str| {
+            if name.contains('.') {
+                Err(format!(
+                    "`{}` contains a `.`, so it could collide with a fresh name",
+                    name
+                ))
+            } else {
+                Ok(())
+            }
+        };
+        match self {
+            Self::Var(x) => check(x),
+            Self::Lam { param, rule } => {
+                check(param)?;
+                rule.validate()
+            }
+            Self::Appl { left, right } => {
+                left.validate()?;
+                right.validate()
+            }
+        }
+    }
+
+    /// Count the nodes in the term, i.e. its vars, lambdas, and applications.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.fold(|_| 1, |_, rule| 1 + rule, |left, right| 1 + left + right)
+    }
+
+    /// Fold the term bottom-up into a single value.
+    ///
+    /// Each node is replaced by calling the function for its variant on the results for its
+    /// children: `var` gets the var's name, `lam` gets the param and the result for the rule,
+    /// and `appl` gets the results for the left and right sides. Children are visited left to
+    /// right, so the closures are called in postorder.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("fn f => fn a => f (f a)")?;
+    /// let depth = term.fold(|_| 0, |_, rule| rule + 1, |left, right| left.max(right) + 1);
+    /// assert_eq!(depth, 4);
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn fold<T>(
+        &self,
+        mut var: impl FnMut(&str) -> T,
+        mut lam: impl FnMut(&str, T) -> T,
+        mut appl: impl FnMut(T, T) -> T,
+    ) -> T {
+        self.fold_impl(&mut var, &mut lam, &mut appl)
+    }
+
+    // Taking the closures by `&mut` means each recursive call reborrows them instead of moving
+    // them, and keeps the generic parameters the same all the way down.
+    fn fold_impl<T, V, L, A>(&self, var: &mut V, lam: &mut L, appl: &mut A) -> T
+    where
+        V: FnMut(&str) -> T,
+        L: FnMut(&str, T) -> T,
+        A: FnMut(T, T) -> T,
+    {
+        match self {
+            Self::Var(x) => var(x),
+            Self::Lam { param, rule } => {
+                let rule = rule.fold_impl(var, lam, appl);
+                lam(param, rule)
+            }
+            Self::Appl { left, right } => {
+                let left = left.fold_impl(var, lam, appl);
+                let right = right.fold_impl(var, lam, appl);
+                appl(left, right)
+            }
+        }
+    }
+}
+
+impl Term {
+    /// Format the term in the given style.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, LambdaStyle};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let two = to_term("fn f => fn a => f (f a)")?;
+    /// assert_eq!(two.display_with(LambdaStyle::BACKSLASH), r"\f.\a.f (f a)");
+    /// assert_eq!(two.display_with(LambdaStyle::GREEK), "λf.λa.f (f a)");
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn display_with(&self, style: LambdaStyle) -> String {
+        let mut out = String::new();
+        // writing to a string never fails
+        let _ = self.write_styled(&style, &mut out);
+        out
+    }
+
+    /// Format the term, breaking lines to keep them within `width` columns where possible.
+    ///
+    /// Any subterm that fits on the rest of its line is printed as `Display` would. Otherwise a
+    /// lambda's body goes on the next line, and an application's arguments go on a line each,
+    /// indented two more spaces than the head. Parens go in the same places as in `Display`, so
+    /// the output parses back to the same term, as long as its idents parse; strip fresh ones
+    /// first with `strip_fresh_suffixes`. Deeply nested terms and long idents can still overflow,
+    /// since the indentation and idents can't be broken.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("fn f => fn a => f (g a a) (fn x => x)")?;
+    /// assert_eq!(term.pretty(80), "fn f => fn a => f (g a a) (fn x => x)");
+    /// assert_eq!(
+    ///     term.pretty(20),
+    ///     "fn f => fn a =>\n  f\n    (g a a)\n    (fn x => x)"
+    /// );
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn pretty(&self, width: usize) -> String {
+        let mut flat_widths = Map::new();
+        self.flat_width(&mut flat_widths);
+        Pretty { width, flat_widths }.format(self, 0, 0)
+    }
+
+    /// Work out how wide each subterm is on one line, as `Display` formats it.
+    ///
+    /// The widths go in `flat_widths`, keyed by the subterm's address, and the term's own width
+    /// is returned.
+    fn flat_width(&self, flat_widths: &mut Map<*const Self, usize>) -> usize {
+        let style = LambdaStyle::default();
+        let width = match self {
+            Self::Var(x) => x.len(),
+            Self::Lam { param, rule } => {
+                style.binder.len()
+                    + param.len()
+                    + style.separator.len()
+                    + rule.flat_width(flat_widths)
+            }
+            Self::Appl { left, right } => {
+                let (left_parens, right_parens) = Self::appl_parens(left, right);
+                let parens = 2 * (usize::from(left_parens) + usize::from(right_parens));
+                left.flat_width(flat_widths) + 1 + right.flat_width(flat_widths) + parens
+            }
+        };
+        flat_widths.insert(self, width);
+        width
+    }
+    /// Write the term in the given style.
+    ///
+    /// This walks the term with an explicit stack of what's left to write, rather than
+    /// recursing, so deep terms like big numerals don't overflow the stack.
+    fn write_styled(&self, style: &LambdaStyle, f: &mut dyn core::fmt::Write) -> core::fmt::Result {
+        enum Piece<'a> {
+            Term(&'a Term),
+            Text(&'a str),
+        }
+
+        let mut todo = vec![Piece::Term(self)];
+        while let Some(piece) = todo.pop() {
+            match piece {
+                Piece::Text(text) => f.write_str(text)?,
+                Piece::Term(Self::Var(x)) => f.write_str(x)?,
+                Piece::Term(Self::Lam { param, rule }) => {
+                    f.write_str(style.binder)?;
+                    f.write_str(param)?;
+                    f.write_str(style.separator)?;
+                    todo.push(Piece::Term(rule));
+                }
+                Piece::Term(Self::Appl { left, right }) => {
+                    let (left_parens, right_parens) = Self::appl_parens(left, right);
+                    let parens = |parens: bool| if parens { ("(", ")") } else { ("", "") };
+                    let ((left_open, left_close), (right_open, right_close)) =
+                        (parens(left_parens), parens(right_parens));
+                    // the stack is last in, first out, so push the pieces in reverse
+                    todo.extend([
+                        Piece::Text(right_close),
+                        Piece::Term(right),
+                        Piece::Text(right_open),
+                        Piece::Text(" "),
+                        Piece::Text(left_close),
+                        Piece::Term(left),
+                        Piece::Text(left_open),
+                    ]);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Format the term, passing each subterm's formatted string through `hook`.
+    ///
+    /// `hook` sees the subterm and its formatting (without any parentheses its parent adds), and
+    /// returns what to print in its place. This is the extension point for things like
+    /// highlighting; with the identity, it's the same as `Display`, which writes the term out
+    /// directly instead of building every subterm's string.
+    #[cfg(feature = "std")]
+    pub(crate) fn fmt_with(&self, hook: &dyn Fn(&Self, String) -> String) -> String {
+        let style = LambdaStyle::default();
+        // Each subterm is popped twice: first to push its children, and then, once they're
+        // formatted and waiting on `done`, to format it.
+        let mut todo = vec![(self, false)];
+        let mut done: Vec<String> = vec![];
+        while let Some((term, children_done)) = todo.pop() {
+            if !children_done {
+                todo.push((term, true));
+                match term {
+                    Self::Var(_) => {}
+                    Self::Lam { rule, .. } => todo.push((rule, false)),
+                    Self::Appl { left, right } => todo.extend([(&**right, false), (left, false)]),
+                }
+                continue;
+            }
+            let mut pop = || done.pop().expect("children are formatted first");
+            let message = match term {
+                Self::Var(x) => x.clone(),
+                Self::Lam { param, .. } => {
+                    format!("{}{}{}{}", style.binder, param, style.separator, pop())
+                }
+                Self::Appl { left, right } => {
+                    let (right_fmt, left_fmt) = (pop(), pop());
+                    let parenthesize = |parens: bool, s: String| {
+                        if parens {
+                            format!("({})", s)
+                        } else {
+                            s
+                        }
+                    };
+                    let (left_parens, right_parens) = Self::appl_parens(left, right);
+                    parenthesize(left_parens, left_fmt)
+                        + " "
+                        + &parenthesize(right_parens, right_fmt)
+                }
+            };
+            done.push(hook(term, message));
+        }
+        done.pop().expect("the term itself is formatted last")
+    }
+
+    /// Whether to parenthesize the left and right sides of an appl when formatting it.
+    //
+    // This parenthesization is invertible, i.e. we don't drop any associativity information and
+    // so `to_term(t.to_string())` always produces the original term, as long as its idents parse.
+    // That's checked by the `display_round_trip` tests in `parse`; the reasoning is explained in
+    // the comments below.
+    fn appl_parens(left: &Self, right: &Self) -> (bool, bool) {
+        // parenthesize lambdas on the left: consider `(fn x => x) g` vs `fn x => x g`
+        //
+        // no need to parenthesize vars, ever
+        //
+        // no need to parenthesize left-heavy appls because of associativity
+        let left_parens = matches!(left, Self::Lam { .. });
+        // parenthesize appls on the right: consider `x y z` vs `x (y z)`
+        //
+        // parenthesize lambdas on the right too. The parser does accept `x fn y => y`, but the
+        // lambda's body extends as far right as it can, so if this appl is itself on the left of
+        // another, the parens are needed: consider `(x (fn y => y)) z`, which would print as
+        // `x fn y => y z`. They also read better: consider `(fn x => xx) fn x => xx` vs
+        // `(fn x => xx) (fn x => xx)`
+        let right_parens = !matches!(right, Self::Var(_));
+        (left_parens, right_parens)
+    }
+}
+
+/// The state for `Term::pretty`.
+struct Pretty {
+    /// The width to keep lines within.
+    width: usize,
+    /// How wide each subterm is on one line, from `Term::flat_width`.
+    flat_widths: Map<*const Term, usize>,
+}
+
+impl Pretty {
+    /// Format `term`, with the first line starting at column `indent`, and `suffix` more columns
+    /// of closing parens after the last line.
+    fn format(&self, term: &Term, indent: usize, suffix: usize) -> String {
+        if indent + self.flat_widths[&core::ptr::from_ref(term)] + suffix <= self.width {
+            return term.to_string();
+        }
+        let newline = |indent: usize| format!("\n{}", " ".repeat(indent));
+        match term {
+            Term::Var(x) => x.clone(),
+            Term::Lam { .. } => {
+                // keep all the params on the first line
+                let mut binders = vec![];
+                let mut body = term;
+                while let Term::Lam { param, rule } = body {
+                    binders.push(format!("fn {} =>", param));
+                    body = rule;
+                }
+                binders.join(" ") + &newline(indent + 2) + &self.format(body, indent + 2, suffix)
+            }
+            Term::Appl { .. } => {
+                let (head, args) = term.spine();
+                // parenthesize the same things `Display` does
+                let parenthesized = |term: &Term, indent: usize, suffix: usize| match term {
+                    Term::Var(x) => x.clone(),
+                    _ => format!("({})", self.format(term, indent + 1, suffix + 1)),
                 };
-                let right_fmt = if let Self::Var(_) = right {
-                    // no need to parenthesize vars, ever
-                    right.to_string()
-                } else {
-                    // parenthesize appls on the right: consider `x y z` vs `x (y z)`
-                    //
-                    // no need to parenthesize lambdas on the right: `fn` sort of does this for us,
-                    // but we do it anyway for readability: consider
-                    // `(fn x => xx) fn x => xx` vs `(fn x => xx) (fn x => xx)`
-                    format!("({})", right)
+                let mut out = match head {
+                    Term::Lam { .. } => parenthesized(head, indent, 0),
+                    _ => self.format(head, indent, 0),
                 };
-                left_fmt + " " + &right_fmt
+                let last = args.len() - 1;
+                for (i, arg) in args.into_iter().enumerate() {
+                    out += &newline(indent + 2);
+                    out += &parenthesized(arg, indent + 2, if i == last { suffix } else { 0 });
+                }
+                out
             }
-        };
-        write!(f, "{}", message)
+        }
+    }
+}
+
+/// The tokens to write lambdas with, for `Term::display_with`.
+///
+/// A lambda is written as the binder, then the param, then the separator, then the body, so any
+/// whitespace has to be part of the tokens. Note that the styles using `.` are ambiguous for
+/// terms with fresh idents like `x.1`; strip those first with `Term::strip_fresh_suffixes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LambdaStyle<'a> {
+    /// What goes before the param.
+    pub binder: &'a str,
+    /// What goes between the param and the body.
+    pub separator: &'a str,
+}
+
+impl LambdaStyle<'static> {
+    /// Our own syntax, `fn x => x`. This is what `Display` uses.
+    pub const FN: Self = Self {
+        binder: "fn ",
+        separator: " => ",
+    };
+
+    /// ASCII lambda calculus syntax, `\x.x`.
+    pub const BACKSLASH: Self = Self {
+        binder: "\\",
+        separator: ".",
+    };
+
+    /// Textbook syntax, `λx.x`.
+    pub const GREEK: Self = Self {
+        binder: "λ",
+        separator: ".",
+    };
+}
+
+impl Default for LambdaStyle<'static> {
+    fn default() -> Self {
+        Self::FN
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.write_styled(&LambdaStyle::default(), f)
     }
 }
 
 /// A named lambda term, for later substitution.
-#[derive(Debug, PartialEq)]
+///
+/// `==` compares the terms up to alpha-equivalence, like it does for `Term`; use
+/// `structural_eq` if the param names matter too.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Defn {
     name: String,
     term: Term,
+    #[cfg_attr(feature = "serde", serde(default))]
+    arity: Option<usize>,
 }
 
 impl Defn {
     /// Create a new `Defn`.
     #[must_use]
     pub const fn new(name: String, term: Term) -> Self {
-        Self { name, term }
+        Self {
+            name,
+            term,
+            arity: None,
+        }
+    }
+
+    /// Annotate the defn with how many arguments it expects; see `check_arity`.
+    #[must_use]
+    pub const fn with_arity(mut self, arity: usize) -> Self {
+        self.arity = Some(arity);
+        self
     }
 
     /// Get a reference to the defn's name.
@@ -121,18 +769,134 @@ impl Defn {
     pub const fn term(&self) -> &Term {
         &self.term
     }
+
+    /// Get the defn's arity annotation, if it has one.
+    #[must_use]
+    pub const fn arity(&self) -> Option<usize> {
+        self.arity
+    }
+
+    /// Check whether two defns are exactly the same, including the names of bound variables.
+    ///
+    /// This is stricter than `==`, which compares the terms up to alpha-equivalence.
+    #[must_use]
+    pub fn structural_eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.arity == other.arity && self.term.structural_eq(&other.term)
+    }
+
+    /// Change the defn's name.
+    ///
+    /// This doesn't touch references to the defn; see `File::rename_defn`.
+    pub fn rename(&mut self, new: &str) {
+        self.name = new.to_string();
+    }
+
+    /// Check the defn's term against its arity annotation, if it has one.
+    ///
+    /// The arity is the number of leading lambdas, e.g. `double : 1 := fn n => add n n`. If the
+    /// term as written doesn't match, we try its normal form too, giving up after
+    /// `ARITY_CHECK_STEPS` steps. Other defns aren't substituted in, so a term like
+    /// `compose succ succ` only has the lambdas it's written with.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_file;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let file = to_file("k : 2 := fn x => fn y => x; i : 2 := fn x => x; main := k")?;
+    /// assert!(file.defns()[0].check_arity());
+    /// assert!(!file.defns()[1].check_arity());
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn check_arity(&self) -> bool {
+        let Some(arity) = self.arity else {
+            return true;
+        };
+        self.term.leading_lambdas() == arity
+            || self
+                .term
+                .clone()
+                .reduce_bounded(ARITY_CHECK_STEPS)
+                .is_ok_and(|normal| normal.leading_lambdas() == arity)
+    }
+}
+
+/// How many steps `Defn::check_arity` spends looking for a normal form.
+const ARITY_CHECK_STEPS: usize = 1000;
+
+impl Term {
+    /// Count the lambdas at the start of the term.
+    fn leading_lambdas(&self) -> usize {
+        let mut term = self;
+        let mut count = 0;
+        while let Self::Lam { rule, .. } = term {
+            term = rule;
+            count += 1;
+        }
+        count
+    }
 }
 
 impl Display for Defn {
     // Displaying `defn` does not include the closing ;, because a) that's how it's implemented in
     // the grammar, and b) I think it looks better that way.
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} := {}", self.name, self.term)
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.arity {
+            Some(arity) => write!(f, "{} : {} := {}", self.name, arity, self.term),
+            None => write!(f, "{} := {}", self.name, self.term),
+        }
+    }
+}
+
+/// A file of defns without a main term, for sharing defns between files.
+///
+/// `==` compares the defns' terms up to alpha-equivalence; use `structural_eq` if the param
+/// names matter too.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Library {
+    defns: Vec<Defn>,
+}
+
+impl Library {
+    /// Create a new `Library`.
+    #[must_use]
+    pub const fn new(defns: Vec<Defn>) -> Self {
+        Self { defns }
+    }
+
+    /// Get a reference to the library's defns.
+    #[must_use]
+    pub fn defns(&self) -> &[Defn] {
+        self.defns.as_ref()
+    }
+
+    /// Check whether two libraries are exactly the same, including the names of bound variables.
+    ///
+    /// This is stricter than `==`, which compares the terms up to alpha-equivalence.
+    #[must_use]
+    pub fn structural_eq(&self, other: &Self) -> bool {
+        defns_structural_eq(&self.defns, &other.defns)
+    }
+}
+
+impl Display for Library {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for defn in &self.defns {
+            writeln!(f, "{};", defn)?;
+        }
+        Ok(())
     }
 }
 
 /// A file of defns, with a main term.
+///
+/// `==` compares the terms up to alpha-equivalence; use `structural_eq` if the param names
+/// matter too.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct File {
     defns: Vec<Defn>,
     main: Term,
@@ -157,6 +921,221 @@ impl File {
         &self.main
     }
 
+    /// Check whether two files are exactly the same, including the names of bound variables.
+    ///
+    /// This is stricter than `==`, which compares the terms up to alpha-equivalence.
+    #[must_use]
+    pub fn structural_eq(&self, other: &Self) -> bool {
+        defns_structural_eq(&self.defns, &other.defns) && self.main.structural_eq(&other.main)
+    }
+
+    /// Rename the defn `old` to `new`, along with every reference to it.
+    ///
+    /// References are free occurrences of `old` in any defn or in main; bound occurrences are
+    /// left alone. A binder named `new` that would capture a new reference is renamed out of the
+    /// way, to `new` with primes added, so the file still parses.
+    ///
+    /// # Errors
+    /// Returns a message, leaving the file unchanged, if there's no defn named `old`, if `new`
+    /// isn't a valid ident, or if `new` is already taken, i.e. it's the name of a defn or occurs
+    /// free somewhere in the file. Renaming onto a taken name would change what the file means.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_file;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut file = to_file("id := fn x => x; main := id (fn id => id)")?;
+    /// file.rename_defn("id", "ident")?;
+    /// assert_eq!(file, to_file("ident := fn x => x; main := ident (fn id => id)")?);
+    /// assert!(file.rename_defn("ident", "main").is_err());
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn rename_defn(&mut self, old: &str, new: &str) -> Result<(), String> {
+        if !self.defns.iter().any(|defn| defn.name == old) {
+            return Err(format!("there's no defn named `{}`", old));
+        }
+        if old == new {
+            return Ok(());
+        }
+        if !is_ident(new) {
+            return Err(format!("`{}` isn't a valid name", new));
+        }
+        if self.defns.iter().any(|defn| defn.name == new) {
+            return Err(format!("`{}` is already defined", new));
+        }
+        if self.terms().any(|term| term.occurs_free(new)) {
+            return Err(format!(
+                "`{}` is already used as a free var, which the new references would refer to",
+                new
+            ));
+        }
+
+        let mut names = Names::default();
+        for term in self.terms() {
+            term.accept(&mut names);
+        }
+        let mut taken = names.0.into_iter().map(ToString::to_string).collect();
+        for defn in &mut self.defns {
+            if defn.name == old {
+                defn.rename(new);
+            }
+            defn.term.rename_free_avoiding(old, new, &mut taken);
+        }
+        self.main.rename_free_avoiding(old, new, &mut taken);
+        Ok(())
+    }
+
+    /// Iterate over the defns' terms, then main.
+    fn terms(&self) -> impl Iterator<Item = &Term> {
+        self.defns
+            .iter()
+            .map(|defn| &defn.term)
+            .chain(core::iter::once(&self.main))
+    }
+
+    /// Reorder the defns so that each one comes after every defn it refers to.
+    ///
+    /// This makes a file with defns written in any order ready for `unroll`. Defns that are
+    /// already in order stay where they are. If a name is defined more than once, a reference to
+    /// it means the latest defn of it before the reference, as in `unroll`, or if the reference
+    /// comes before all of them, the last one. Reordering doesn't change which defn that is.
+    ///
+    /// # Errors
+    /// If some defns are recursive, there's no such order, so this returns their names and leaves
+    /// the file unchanged. Use `unroll_recursive`, or `letrec`, for those.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_file;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut file = to_file("two := succ one; one := succ zero; zero := fn f => fn a => a;
+    ///     succ := fn n => fn f => fn a => f (n f a); main := two")?;
+    /// assert_eq!(file.topologically_order_defns(), Ok(()));
+    /// let names: Vec<_> = file.defns().iter().map(|defn| defn.name()).collect();
+    /// assert_eq!(names, vec!["zero", "succ", "one", "two"]);
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn topologically_order_defns(&mut self) -> Result<(), Vec<String>> {
+        let edges = dependencies(&self.defns);
+        let components = strongly_connected(&edges);
+        let recursive: Vec<String> = components
+            .iter()
+            .filter(|component| match component[..] {
+                [i] => edges[i].contains(&i),
+                _ => true,
+            })
+            .flatten()
+            .map(|&i| self.defns[i].name.clone())
+            .collect();
+        if !recursive.is_empty() {
+            return Err(recursive);
+        }
+
+        let mut defns: Vec<Option<Defn>> = self.defns.drain(..).map(Some).collect();
+        self.defns = components
+            .into_iter()
+            .flatten()
+            .filter_map(|i| defns[i].take())
+            .collect();
+        Ok(())
+    }
+
+    /// Look for likely mistakes in the file, returning a warning for each.
+    ///
+    /// For now, this finds names defined more than once. Later defns shadow earlier ones, so the
+    /// earlier defn is only seen by the defns between the two, which is rarely what was meant.
+    /// A `File` doesn't know where its defns were in the source, so they're numbered from one,
+    /// in file order. To lint source, with lines and columns, and without the defns from its
+    /// includes, which it can deliberately override, use `lint_source`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_file;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let file = to_file("id := fn x => x; k := fn x => fn y => x; id := k; main := id")?;
+    /// assert_eq!(
+    ///     file.lint(),
+    ///     ["`id` is defined again as defn 3, shadowing defn 1"]
+    /// );
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn lint(&self) -> Vec<String> {
+        shadowed_defns(self.defns.iter().map(Defn::name))
+            .into_iter()
+            .map(|(earlier, later)| {
+                format!(
+                    "`{}` is defined again as defn {}, shadowing defn {}",
+                    self.defns[later].name,
+                    later + 1,
+                    earlier + 1
+                )
+            })
+            .collect()
+    }
+
+    /// Put the library's defns before the file's own.
+    ///
+    /// Since later defns shadow earlier ones, the file's defns take precedence over the
+    /// library's.
+    #[must_use]
+    pub fn with_library(mut self, lib: Library) -> Self {
+        let mut defns = lib.defns;
+        defns.append(&mut self.defns);
+        self.defns = defns;
+        self
+    }
+
+    /// Combine two files, putting `other`'s defns after this file's, and using `other`'s main.
+    ///
+    /// A name defined in both files is only kept once, from this file, as long as the bodies are
+    /// alpha-equivalent.
+    ///
+    /// # Errors
+    /// Returns a message naming the first defn whose bodies differ between the files.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_file;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let combinators = to_file("id := fn x => x; k := fn x => fn y => x; main := id")?;
+    /// let program = to_file("id := fn a => a; main := k id")?;
+    /// assert_eq!(
+    ///     combinators.merge(program),
+    ///     Ok(to_file("id := fn x => x; k := fn x => fn y => x; main := k id")?)
+    /// );
+    ///
+    /// let clash = to_file("k := fn x => fn y => y; main := k")?;
+    /// assert!(to_file("k := fn x => fn y => x; main := k")?.merge(clash).is_err());
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn merge(mut self, other: Self) -> Result<Self, String> {
+        for defn in other.defns {
+            let mut same_name = self.defns.iter().filter(|ours| ours.name == defn.name);
+            match same_name.next() {
+                None => self.defns.push(defn),
+                Some(ours)
+                    if ours.term == defn.term && same_name.all(|ours| ours.term == defn.term) => {}
+                Some(_) => {
+                    return Err(format!(
+                        "`{}` is defined differently in the two files",
+                        defn.name
+                    ))
+                }
+            }
+        }
+        self.main = other.main;
+        Ok(self)
+    }
+
     /// Unroll the file into a single lambda.
     ///
     /// We think of main as abstracted over each defn in reverse, i.e.
@@ -184,14 +1163,506 @@ impl File {
                 right: defn.term.into(),
             })
     }
+
+    /// Unroll the file into a single lambda, like `unroll`, but keep the file.
+    ///
+    /// This clones main and each defn's name and term once, the same copying as `unroll` on a
+    /// clone of the file, so it's only a convenience for when the file is still needed.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_file;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let file = to_file("id := fn x => x; main := id y")?;
+    /// let term = file.to_unrolled();
+    /// println!("{} unrolls to {}", file, term);
+    /// assert_eq!(term, file.unroll());
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn to_unrolled(&self) -> Term {
+        self.defns
+            .iter()
+            .rev()
+            .fold(self.main.clone(), |main, defn| Term::Appl {
+                left: Term::lambda(&[&defn.name], main).into(),
+                right: defn.term.clone().into(),
+            })
+    }
+
+    /// Unroll the file into a single lambda, allowing recursive defns.
+    ///
+    /// Unlike `unroll`, a defn here can refer to any defn in the file: earlier ones, later ones,
+    /// and itself. We group the defns into strongly connected components of the "refers to"
+    /// graph, and bind each group after the groups it depends on. A self-recursive defn
+    /// `f := t` is bound to `Y (fn f => t)`. A group of mutually recursive defns `f1 ... fn` is
+    /// bound to a single fixpoint
+    /// ```m3lc
+    /// group := Y (fn group => fn s => s t1' ... tn')
+    /// ```
+    /// where each `ti'` is `ti` with every `fj` replaced by `group (fn f1 => ... fn fn => fj)`,
+    /// and then each `fi` is bound to `group (fn f1 => ... fn fn => fi)`.
+    ///
+    /// If a name is defined more than once, only the last defn is used, since there's no sensible
+    /// way to decide which one a recursive reference means.
+    #[must_use]
+    pub fn unroll_recursive(self) -> Term {
+        let mut defns = self.defns;
+        let mut seen = Set::new();
+        defns.reverse();
+        defns.retain(|defn| seen.insert(defn.name.clone()));
+        defns.reverse();
+
+        let edges = dependencies(&defns);
+        let mut bindings = vec![];
+        for component in strongly_connected(&edges) {
+            if let [i] = component[..] {
+                let Defn { name, term, .. } = defns[i].clone();
+                if edges[i].contains(&i) {
+                    // f := Y (fn f => t)
+                    let rule = Term::lambda(&[&name], term);
+                    bindings.push((name, Term::apply_all(Y.clone(), vec![rule])));
+                } else {
+                    bindings.push((name, term));
+                }
+                continue;
+            }
+
+            // Everything we bind here is in scope in the defns' terms, so it all needs fresh
+            // names to avoid capturing anything.
+            let group = get_fresh_ident("group");
+            let select = get_fresh_ident("s");
+            let names: Vec<&str> = component.iter().map(|&i| defns[i].name()).collect();
+            let projection = |j: usize| Term::lambda(&names, names[j].into());
+            let lookup = |j| Term::apply_all(group.as_str().into(), vec![projection(j)]);
+
+            // group := Y (fn group => fn s => s t1' ... tn')
+            let rules = component
+                .iter()
+                .map(|&i| {
+                    let rule = Term::lambda(&names, defns[i].term.clone());
+                    Term::apply_all(rule, (0..names.len()).map(lookup).collect())
+                })
+                .collect();
+            let tuple = Term::lambda(
+                &[&group, &select],
+                Term::apply_all(select.as_str().into(), rules),
+            );
+            let group_term = Term::apply_all(Y.clone(), vec![tuple]);
+
+            // fi := group (fn f1 => ... fn fn => fi)
+            let members: Vec<_> = (0..names.len())
+                .map(|j| (names[j].to_string(), lookup(j)))
+                .collect();
+            bindings.push((group, group_term));
+            bindings.extend(members);
+        }
+
+        bindings
+            .into_iter()
+            .rev()
+            .fold(self.main, |main, (name, term)| Term::Appl {
+                left: Term::lambda(&[&name], main).into(),
+                right: term.into(),
+            })
+    }
+
+    /// Reduce the file to normal form, normalizing each defn only once.
+    ///
+    /// `unroll` substitutes each defn's term wherever it's referenced, so a defn used many
+    /// times gets reduced many times over. Instead, this normalizes each defn, with the earlier
+    /// defns' values substituted in, and substitutes the normal form. Normal forms are cached by
+    /// alpha-equivalence class, so defns that come out alpha-equivalent are only normalized
+    /// once between them.
+    ///
+    /// Defns don't need a normal form to be useful (think `Y`), so each defn gets at most
+    /// `max_defn_steps` steps, and gives up early if it grows much bigger than it started; if it
+    /// doesn't normalize in time, it's substituted as written.
+    /// Either way, the result is alpha-equivalent to `self.unroll().reduce(false)`.
+    ///
+    /// # Safety
+    /// The halting problem is a thing. Ergo, reducing main can cause unhandled infinite regress.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_file, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let file = to_file("succ := fn n => fn f => fn a => f (n f a);
+    ///     two := succ (succ (fn f => fn a => a)); main := two two")?;
+    /// assert!(file.reduce(1000).alpha_equiv(&4.into()));
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn reduce(self, max_defn_steps: usize) -> Term {
+        let mut values = DefnValues::new(max_defn_steps);
+        for defn in self.defns {
+            values.push(defn);
+        }
+        values.substitute(self.main).reduce(false)
+    }
+}
+
+/// The values of the defns seen so far, for `File::reduce`.
+pub(crate) struct DefnValues {
+    // terms are ordered up to alpha-equivalence, so alpha-equivalent terms share an entry
+    cache: BTreeMap<Term, Term>,
+    values: Vec<(String, Term)>,
+    max_defn_steps: usize,
+}
+
+impl DefnValues {
+    pub(crate) const fn new(max_defn_steps: usize) -> Self {
+        Self {
+            cache: BTreeMap::new(),
+            values: vec![],
+            max_defn_steps,
+        }
+    }
+
+    /// Substitute the values of the defns seen so far into `term`.
+    pub(crate) fn substitute(&self, mut term: Term) -> Term {
+        // Each value already has the earlier values substituted in, but a forward reference is
+        // still free in it, like in `unroll`, so this has to be one simultaneous substitution:
+        // one at a time, a later defn would get substituted into an earlier one's value. Only
+        // the last value of each name counts, since later defns shadow earlier ones.
+        let free = term.free_vars();
+        let mut seen = Set::new();
+        let subs: Vec<(&str, &Term)> = self
+            .values
+            .iter()
+            .rev()
+            .filter(|(name, _)| free.contains(name.as_str()) && seen.insert(name.as_str()))
+            .map(|(name, value)| (name.as_str(), value))
+            .collect();
+        term.substitute_many_impl(&subs);
+        term
+    }
+
+    /// Normalize a defn, with the earlier values substituted in, and record its value.
+    pub(crate) fn push(&mut self, Defn { name, term, .. }: Defn) -> &(String, Term) {
+        let term = self.substitute(term);
+        let value = if let Some(normal) = self.cache.get(&term) {
+            normal.clone()
+        } else if let Some(normal) = term.normalize_defn(self.max_defn_steps) {
+            self.cache.insert(term, normal.clone());
+            normal
+        } else {
+            term
+        };
+        self.values.push((name, value));
+        self.values.last().expect("just pushed")
+    }
+}
+
+/// How many times its original size a defn can grow to while `File::reduce` normalizes it.
+///
+/// Something like `Y f` grows by a copy of `f` every few steps forever, and each step costs time
+/// proportional to the size, so without this the step budget alone takes quadratic time to run
+/// out.
+const MAX_DEFN_GROWTH: usize = 16;
+
+impl Term {
+    /// Normalize a defn for `File::reduce`, or give up and return `None`.
+    fn normalize_defn(&self, max_steps: usize) -> Option<Self> {
+        let max_size = self.size().saturating_mul(MAX_DEFN_GROWTH);
+        let mut term = self.clone();
+        for _ in 0..max_steps {
+            if term.size() > max_size {
+                // it might have grown past the cap on the very step that made it normal
+                return term.is_irreducible().then_some(term);
+            }
+            if !term.reduction_step() {
+                return Some(term);
+            }
+        }
+        term.is_irreducible().then_some(term)
+    }
+}
+
+/// Find names defined more than once, for linting.
+///
+/// Given the defns' names in order, returns an `(earlier, later)` pair of indices for each defn
+/// that shadows an earlier one, where `earlier` is the one it shadows.
+pub(crate) fn shadowed_defns<'a>(names: impl Iterator<Item = &'a str>) -> Vec<(usize, usize)> {
+    let mut last_seen: Map<&str, usize> = Map::new();
+    names
+        .enumerate()
+        .filter_map(|(i, name)| last_seen.insert(name, i).map(|earlier| (earlier, i)))
+        .collect()
+}
+
+/// Check whether two lists of defns are pairwise `structural_eq`.
+fn defns_structural_eq(left: &[Defn], right: &[Defn]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| l.structural_eq(r))
+}
+
+/// Whether the parser would accept `name` as an ident.
+fn is_ident(name: &str) -> bool {
+    let body = name.trim_end_matches('\'');
+    let digits = |prefix: &str, radix: u32| {
+        body.strip_prefix(prefix)
+            .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_digit(radix)))
+    };
+    !body.is_empty()
+        && body.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !["fn", "main", "letrec", "in", "_"].contains(&name)
+        && !digits("0x", 16)
+        && !digits("0b", 2)
+}
+
+/// Collects every var and param name in a term, for `File::rename_defn`.
+#[derive(Default)]
+struct Names<'a>(Set<&'a str>);
+
+impl<'a> Visitor<'a> for Names<'a> {
+    fn visit_var(&mut self, name: &'a str) {
+        self.0.insert(name);
+    }
+
+    fn visit_lam(&mut self, param: &'a str, _: &'a Term) {
+        self.0.insert(param);
+    }
+}
+
+impl Term {
+    /// Rename every free occurrence of `from` to `to`, like `rename_free`, but rename capturing
+    /// binders to `to` with primes added, skipping the names in `taken`.
+    ///
+    /// `rename_free` uses fresh names for those binders, which the parser won't accept. `taken`
+    /// should hold every name in the term, and gets the new binder names added to it.
+    fn rename_free_avoiding(&mut self, from: &str, to: &str, taken: &mut Set<String>) {
+        match self {
+            Self::Var(x) => {
+                if x == from {
+                    *x = to.to_string();
+                }
+            }
+            Self::Lam { param, rule } => {
+                if param == from {
+                    return;
+                }
+                if param == to && rule.occurs_free(from) {
+                    let mut renamed = format!("{}'", to);
+                    while taken.contains(&renamed) {
+                        renamed.push('\'');
+                    }
+                    // nothing in the rule is named `renamed`, so this can't capture anything
+                    rule.rename_free(to, &renamed);
+                    taken.insert(renamed.clone());
+                    *param = renamed;
+                }
+                rule.rename_free_avoiding(from, to, taken);
+            }
+            Self::Appl { left, right } => {
+                left.rename_free_avoiding(from, to, taken);
+                right.rename_free_avoiding(from, to, taken);
+            }
+        }
+    }
+}
+
+/// Build the "refers to" graph of some defns, as adjacency lists of indices into `defns`.
+///
+/// A reference points at the latest defn of that name before the one it's in, which is what it
+/// means to `unroll`, or if there isn't one, at the last defn of that name. If a name is defined
+/// more than once, each of its defns also gets edges to the one before it, and to everything
+/// referring to the one before it, so any order that respects the graph keeps every reference
+/// pointing at the same defn, and keeps the last defn of each name last. Each list is sorted, so
+/// anything that walks the graph does so deterministically.
+fn dependencies(defns: &[Defn]) -> Vec<Vec<usize>> {
+    let mut by_name: Map<&str, Vec<usize>> = Map::new();
+    for (i, defn) in defns.iter().enumerate() {
+        by_name.entry(defn.name()).or_default().push(i);
+    }
+    let mut edges: Vec<Vec<usize>> = defns
+        .iter()
+        .enumerate()
+        .map(|(i, defn)| {
+            defn.term
+                .free_vars()
+                .into_iter()
+                .filter_map(|name| {
+                    let same = by_name.get(name)?;
+                    match same.partition_point(|&j| j < i) {
+                        0 => same.last().copied(),
+                        before => Some(same[before - 1]),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    // the next defn with the same name as each defn, if there is one
+    let mut next = vec![None; defns.len()];
+    for same in by_name.values() {
+        for pair in same.windows(2) {
+            next[pair[0]] = Some(pair[1]);
+        }
+    }
+    let mut shadowing = vec![vec![]; defns.len()];
+    for (i, deps) in edges.iter().enumerate() {
+        for &dep in deps {
+            match next[dep] {
+                Some(later) if later != i => shadowing[later].push(i),
+                _ => {}
+            }
+        }
+    }
+    for (i, later) in next.into_iter().enumerate() {
+        if let Some(later) = later {
+            shadowing[later].push(i);
+        }
+    }
+
+    for (deps, mut shadowing) in edges.iter_mut().zip(shadowing) {
+        deps.append(&mut shadowing);
+        deps.sort_unstable();
+        deps.dedup();
+    }
+    edges
+}
+
+/// Find the strongly connected components of a graph, given as adjacency lists.
+///
+/// This is Tarjan's algorithm, which conveniently emits each component after every component it
+/// has edges into, i.e. each defn comes after the defns it refers to. Within a component, nodes
+/// are sorted.
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        edges: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        out: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, v: usize) {
+            self.index[v] = Some(self.next);
+            self.low[v] = self.next;
+            self.next += 1;
+            self.stack.push(v);
+            self.on_stack[v] = true;
+
+            for &w in &self.edges[v] {
+                match self.index[w] {
+                    None => {
+                        self.visit(w);
+                        self.low[v] = self.low[v].min(self.low[w]);
+                    }
+                    Some(index) if self.on_stack[w] => self.low[v] = self.low[v].min(index),
+                    Some(_) => (),
+                }
+            }
+
+            // v is the root of a component, which is everything above it on the stack
+            if Some(self.low[v]) == self.index[v] {
+                let mut component = vec![];
+                while let Some(w) = self.stack.pop() {
+                    self.on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                self.out.push(component);
+            }
+        }
+    }
+
+    let n = edges.len();
+    let mut tarjan = Tarjan {
+        edges,
+        index: vec![None; n],
+        low: vec![0; n],
+        on_stack: vec![false; n],
+        stack: vec![],
+        next: 0,
+        out: vec![],
+    };
+    for v in 0..n {
+        if tarjan.index[v].is_none() {
+            tarjan.visit(v);
+        }
+    }
+    tarjan.out
+}
+
+impl Display for File {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for defn in &self.defns {
+            writeln!(f, "{};", defn)?;
+        }
+        write!(f, "main := {};", self.main)
+    }
+}
+
+impl Extend<Defn> for File {
+    /// Add defns after the file's own, so they shadow any earlier defns with the same name.
+    fn extend<T: IntoIterator<Item = Defn>>(&mut self, iter: T) {
+        self.defns.extend(iter);
+    }
+}
+
+/// Builds a `File` one defn at a time, for generating programs.
+///
+/// # Example
+/// ```
+/// # use m3lc::{to_file, to_term, FileBuilder};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #
+/// let file = FileBuilder::new()
+///     .defn("id", to_term("fn x => x")?)
+///     .defn("two", to_term("fn f => fn a => f (f a)")?)
+///     .main(to_term("two id")?);
+/// assert_eq!(file, to_file("id := fn x => x; two := fn f => fn a => f (f a); main := two id")?);
+/// #
+/// # Ok(())}
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileBuilder {
+    defns: Vec<Defn>,
+}
+
+impl FileBuilder {
+    /// Create a new `FileBuilder`, with no defns.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { defns: vec![] }
+    }
+
+    /// Add a defn after the ones so far.
+    #[must_use]
+    pub fn defn(mut self, name: &str, term: Term) -> Self {
+        self.defns.push(Defn::new(name.to_string(), term));
+        self
+    }
+
+    /// Finish the file with its main.
+    #[must_use]
+    pub fn main(self, main: Term) -> File {
+        File::new(self.defns, main)
+    }
+}
+
+impl Extend<Defn> for FileBuilder {
+    fn extend<T: IntoIterator<Item = Defn>>(&mut self, iter: T) {
+        self.defns.extend(iter);
+    }
 }
 
-impl Display for File {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for defn in &self.defns {
-            writeln!(f, "{};", defn)?;
+impl FromIterator<Defn> for FileBuilder {
+    fn from_iter<T: IntoIterator<Item = Defn>>(iter: T) -> Self {
+        Self {
+            defns: iter.into_iter().collect(),
         }
-        write!(f, "main := {};", self.main)
     }
 }
 
@@ -200,6 +1671,15 @@ mod tests {
     use super::*;
     use Term::{Appl, Lam, Var};
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip() -> crate::ParserResult<()> {
+        let term = crate::to_term("(fn f => fn a => f (f a)) (fn x => x y)")?;
+        let json = serde_json::to_string(&term).expect("terms always serialize");
+        assert_eq!(serde_json::from_str::<Term>(&json).ok(), Some(term));
+        Ok(())
+    }
+
     macro_rules! term_display_tests { ($($name:ident: $expected:expr, $ast:expr)*)  => {
     mod term_display {
         use super::*;
@@ -280,6 +1760,7 @@ mod tests {
                 param: "x".into(),
                 rule: "x".into(),
             },
+            arity: None,
         };
         assert_eq!(format!("{}", defn), "ident := fn x => x");
     }
@@ -293,6 +1774,7 @@ mod tests {
                     param: "x".into(),
                     rule: "x".into(),
                 },
+                arity: None,
             },
             Defn {
                 name: "zero".into(),
@@ -304,6 +1786,7 @@ mod tests {
                     }
                     .into(),
                 },
+                arity: None,
             },
         ];
         let main = Appl {
@@ -328,6 +1811,7 @@ mod tests {
                     param: "x".into(),
                     rule: "x".into(),
                 },
+                arity: None,
             },
             Defn {
                 name: "zero".into(),
@@ -339,6 +1823,7 @@ mod tests {
                     }
                     .into(),
                 },
+                arity: None,
             },
         ];
         let main = Appl {
@@ -380,4 +1865,749 @@ mod tests {
         };
         assert_eq!(input.unroll(), expected);
     }
+
+    mod display_with {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn default_is_display() -> ParserResult<()> {
+            let term = to_term("(fn x => x x) (fn y => y z)")?;
+            assert_eq!(term.display_with(LambdaStyle::default()), term.to_string());
+            Ok(())
+        }
+
+        #[test]
+        fn backslash() -> ParserResult<()> {
+            let term = to_term("(fn x => x x) (fn y => y (z w))")?;
+            assert_eq!(
+                term.display_with(LambdaStyle::BACKSLASH),
+                r"(\x.x x) (\y.y (z w))"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn custom() -> ParserResult<()> {
+            let style = LambdaStyle {
+                binder: "lambda ",
+                separator: ": ",
+            };
+            let term = to_term("fn f => fn a => f a")?;
+            assert_eq!(term.display_with(style), "lambda f: lambda a: f a");
+            Ok(())
+        }
+    }
+
+    mod pretty {
+        use crate::{to_term, ParserResult, Term};
+
+        const FIBBIT: &str = include_str!("../examples/fibbit.m3lc");
+
+        #[test]
+        fn fits() -> ParserResult<()> {
+            let term = to_term("(fn x => x x) (fn y => y z)")?;
+            assert_eq!(term.pretty(80), term.to_string());
+            Ok(())
+        }
+
+        #[test]
+        fn breaks() -> ParserResult<()> {
+            let term = to_term("(fn x => fn y => x y) (fn z => z z) w")?;
+            let expected = "(fn x => fn y =>\n   x y)\n  (fn z => z z)\n  w";
+            assert_eq!(term.pretty(16), expected);
+            Ok(())
+        }
+
+        #[test]
+        fn lines_fit() -> ParserResult<()> {
+            let term = to_term("fn f => f aa bb cc dd ee (gg hh (ii jj)) kk")?;
+            for line in term.pretty(16).lines() {
+                assert!(line.len() <= 16, "`{}` is too long", line);
+            }
+            Ok(())
+        }
+
+        #[test]
+        /// Pretty output parses back to the same term, at any width.
+        fn round_trip() -> ParserResult<()> {
+            let term = crate::to_file(FIBBIT)?.unroll();
+            for width in [0, 20, 40, 80] {
+                assert_structural_eq!(to_term(&term.pretty(width))?, term);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn lambda_head() -> ParserResult<()> {
+            let term: Term = to_term("(fn x => x) (fn y => y) (a b)")?;
+            assert_structural_eq!(to_term(&term.pretty(0))?, term);
+            Ok(())
+        }
+    }
+
+    mod ord {
+        use core::cmp::Ordering;
+
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        /// Var < Lam < Appl, regardless of contents.
+        fn variants() -> ParserResult<()> {
+            let var = to_term("z")?;
+            let lam = to_term("fn a => a")?;
+            let appl = to_term("a a")?;
+            assert!(var < lam && lam < appl);
+            Ok(())
+        }
+
+        #[test]
+        fn lexicographic() -> ParserResult<()> {
+            assert!(to_term("a")? < to_term("b")?);
+            // params don't count, so this goes by the free vars in the bodies
+            assert!(to_term("fn a => z")? > to_term("fn b => a")?);
+            assert!(to_term("fn a => a")? < to_term("fn a => b")?);
+            assert!(to_term("a (b c)")? < to_term("b a")?);
+            Ok(())
+        }
+
+        #[test]
+        /// Param names don't matter, just which binder each var refers to.
+        fn alpha_equivalence() -> ParserResult<()> {
+            let (x, y) = (to_term("fn x => x z")?, to_term("fn y => y z")?);
+            assert_eq!(x.cmp(&y), Ordering::Equal);
+            assert!(to_term("fn a => fn b => a")? < to_term("fn a => fn b => b")?);
+            assert!(to_term("fn a => a")? < to_term("fn b => a")?);
+            Ok(())
+        }
+
+        #[test]
+        fn sorting() -> ParserResult<()> {
+            let mut terms = vec![
+                to_term("x y")?,
+                to_term("fn x => x")?,
+                to_term("y")?,
+                to_term("x")?,
+            ];
+            terms.sort();
+            assert_eq!(
+                terms,
+                vec![
+                    to_term("x")?,
+                    to_term("y")?,
+                    to_term("fn x => x")?,
+                    to_term("x y")?
+                ]
+            );
+            Ok(())
+        }
+    }
+
+    mod size {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn var() {
+            assert_eq!(Term::from("x").size(), 1);
+        }
+
+        #[test]
+        fn numeral() {
+            // two lambdas, and then `f (f a)` is two appls and three vars
+            assert_eq!(Term::from(2).size(), 7);
+        }
+
+        #[test]
+        fn omega() -> ParserResult<()> {
+            assert_eq!(to_term("(fn x => x x) (fn x => x x)")?.size(), 9);
+            Ok(())
+        }
+    }
+
+    mod fold {
+        use core::cell::RefCell;
+
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn rebuild() -> ParserResult<()> {
+            let term = to_term("(fn x => x y) (fn f => fn a => f a)")?;
+            let rebuilt = term.fold(
+                |x| x.into(),
+                |param, rule| Term::lambda(&[param], rule),
+                |left, right| Term::apply_all(left, vec![right]),
+            );
+            assert_structural_eq!(rebuilt, term);
+            Ok(())
+        }
+
+        #[test]
+        fn postorder() -> ParserResult<()> {
+            let order = RefCell::new(vec![]);
+            to_term("fn x => y z")?.fold(
+                |x| order.borrow_mut().push(x.to_string()),
+                |param, ()| order.borrow_mut().push(param.to_string()),
+                |(), ()| (),
+            );
+            assert_eq!(order.into_inner(), vec!["y", "z", "x"]);
+            Ok(())
+        }
+    }
+
+    mod spine {
+        use super::*;
+
+        #[test]
+        fn apply_all() {
+            let expected = Appl {
+                left: Appl {
+                    left: "f".into(),
+                    right: "a".into(),
+                }
+                .into(),
+                right: Lam {
+                    param: "x".into(),
+                    rule: "x".into(),
+                }
+                .into(),
+            };
+            let got = Term::apply_all(
+                "f".into(),
+                vec![
+                    "a".into(),
+                    Lam {
+                        param: "x".into(),
+                        rule: "x".into(),
+                    },
+                ],
+            );
+            assert_structural_eq!(got, expected);
+        }
+
+        #[test]
+        fn apply_all_no_args() {
+            assert_eq!(Term::apply_all("f".into(), vec![]), "f".into());
+        }
+
+        #[test]
+        fn spine() {
+            let term = Term::apply_all("f".into(), vec!["a".into(), "b".into(), "c".into()]);
+            let (head, args) = term.spine();
+            assert_eq!(head, &Var("f".into()));
+            assert_eq!(
+                args,
+                vec![&Var("a".into()), &Var("b".into()), &Var("c".into())]
+            );
+        }
+
+        #[test]
+        fn spine_right_nested() {
+            // `f (g a)` has one argument, the whole of `g a`
+            let term = Appl {
+                left: "f".into(),
+                right: Appl {
+                    left: "g".into(),
+                    right: "a".into(),
+                }
+                .into(),
+            };
+            let (head, args) = term.spine();
+            assert_eq!(head, &Var("f".into()));
+            assert_eq!(args.len(), 1);
+        }
+
+        #[test]
+        fn spine_lam() {
+            let term = Lam {
+                param: "x".into(),
+                rule: "x".into(),
+            };
+            assert_eq!(term.spine(), (&term, vec![]));
+        }
+    }
+
+    mod lambda {
+        use super::*;
+
+        #[test]
+        fn identity() {
+            let expected = Lam {
+                param: "x".into(),
+                rule: "x".into(),
+            };
+            assert_structural_eq!(Term::lambda(&["x"], "x".into()), expected);
+        }
+
+        #[test]
+        fn no_params() {
+            assert_eq!(Term::lambda(&[], "x".into()), "x".into());
+        }
+
+        #[test]
+        fn zero() {
+            let expected = Lam {
+                param: "f".into(),
+                rule: Lam {
+                    param: "a".into(),
+                    rule: "a".into(),
+                }
+                .into(),
+            };
+            assert_structural_eq!(Term::lambda(&["f", "a"], "a".into()), expected);
+        }
+    }
+
+    mod check_arity {
+        use crate::{to_file, ParserResult};
+
+        macro_rules! check_arity_tests { ($($name:ident: $input:expr, $expected:expr)*) => {
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let file = to_file(&format!("{}; main := x", $input))?;
+                assert_eq!(file.defns()[0].check_arity(), $expected);
+                Ok(())
+            }
+            )*
+        }}
+
+        check_arity_tests! {
+            unannotated: "k := fn x => fn y => x", true
+            zero: "z : 0 := x y", true
+            written: "k : 2 := fn x => fn y => x", true
+            too_many: "k : 1 := fn x => fn y => x", false
+            too_few: "k : 3 := fn x => fn y => x", false
+            after_reduction: "k : 2 := (fn x => x) (fn x => fn y => x)", true
+            // there's no normal form to check
+            diverges: "o : 1 := (fn x => x x) (fn x => x x)", false
+        }
+    }
+
+    mod merge {
+        use crate::{to_file, Defn, ParserResult};
+
+        #[test]
+        fn disjoint() -> ParserResult<()> {
+            let left = to_file("a := x; main := a")?;
+            let right = to_file("b := y; main := b")?;
+            assert_eq!(left.merge(right), Ok(to_file("a := x; b := y; main := b")?));
+            Ok(())
+        }
+
+        #[test]
+        /// Alpha-equivalent defns aren't duplicated.
+        fn shared() -> ParserResult<()> {
+            let left = to_file("id := fn x => x; main := id")?;
+            let right = to_file("id := fn y => y; two := id id; main := two")?;
+            let merged = left.merge(right).expect("the defns agree");
+            let names: Vec<_> = merged.defns().iter().map(Defn::name).collect();
+            assert_eq!(names, vec!["id", "two"]);
+            Ok(())
+        }
+
+        #[test]
+        fn conflict() -> ParserResult<()> {
+            let left = to_file("id := fn x => x; main := id")?;
+            let right = to_file("id := fn x => y; main := id")?;
+            assert_eq!(
+                left.merge(right),
+                Err("`id` is defined differently in the two files".to_string())
+            );
+            Ok(())
+        }
+
+        #[test]
+        /// A name shadowed within one file conflicts if any of its bodies differ.
+        fn shadowed() -> ParserResult<()> {
+            let left = to_file("a := x; a := y; main := a")?;
+            let right = to_file("a := y; main := a")?;
+            assert!(left.merge(right).is_err());
+            Ok(())
+        }
+    }
+
+    mod rename_defn {
+        use crate::{to_file, to_term, ParserResult};
+
+        #[test]
+        fn missing() -> ParserResult<()> {
+            let mut file = to_file("id := fn x => x; main := id")?;
+            assert!(file.rename_defn("nope", "other").is_err());
+            assert_eq!(file, to_file("id := fn x => x; main := id")?);
+            Ok(())
+        }
+
+        #[test]
+        fn references_in_defns() -> ParserResult<()> {
+            let mut file = to_file("id := fn x => x; twice := fn f => id f (id f); main := twice")?;
+            assert_eq!(file.rename_defn("id", "i"), Ok(()));
+            let expected = to_file("i := fn x => x; twice := fn f => i f (i f); main := twice")?;
+            assert_eq!(file, expected);
+            Ok(())
+        }
+
+        #[test]
+        /// A binder with the new name would capture the new reference, so it gets renamed, to
+        /// something the parser accepts.
+        fn avoids_capture() -> ParserResult<()> {
+            let mut file = to_file("id := fn x => x; main := fn i => id i")?;
+            assert_eq!(file.rename_defn("id", "i"), Ok(()));
+            assert_eq!(file.main().to_string(), "fn i' => i i'");
+            let reparsed = to_file(&file.to_string())?;
+            assert_structural_eq!(reparsed.main(), file.main());
+            Ok(())
+        }
+
+        #[test]
+        /// Binders are renamed past names that are already used.
+        fn avoids_taken_primes() -> ParserResult<()> {
+            let mut file = to_file("id := fn x => x; main := fn i => fn i' => id i i'")?;
+            assert_eq!(file.rename_defn("id", "i"), Ok(()));
+            assert_structural_eq!(file.main(), to_term("fn i'' => fn i' => i i'' i'")?);
+            Ok(())
+        }
+
+        #[test]
+        /// Renaming onto another defn's name would leave two defns with that name.
+        fn defn_name_taken() -> ParserResult<()> {
+            let input = "i := fn a => fn b => a; id := fn x => x; main := i";
+            let mut file = to_file(input)?;
+            assert!(file.rename_defn("id", "i").is_err());
+            assert_eq!(file, to_file(input)?);
+            Ok(())
+        }
+
+        #[test]
+        /// Renaming onto a free var would make it refer to the defn.
+        fn free_var_taken() -> ParserResult<()> {
+            let input = "id := fn x => x; main := id y";
+            let mut file = to_file(input)?;
+            assert!(file.rename_defn("id", "y").is_err());
+            assert_eq!(file, to_file(input)?);
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_ident() -> ParserResult<()> {
+            let mut file = to_file("id := fn x => x; main := id")?;
+            for new in ["", "x.1", "fn", "main", "_", "0x1f", "0b10", "a'b", "a b"] {
+                assert!(
+                    file.rename_defn("id", new).is_err(),
+                    "{:?} was accepted",
+                    new
+                );
+            }
+            for new in ["x'", "_x", "0x1g", "0b12", "fn'", "main_"] {
+                assert_eq!(file.rename_defn("id", new), Ok(()));
+                assert_eq!(to_file(&file.to_string())?, file);
+                file.rename_defn(new, "id").expect("id is free again");
+            }
+            Ok(())
+        }
+    }
+
+    mod topologically_order_defns {
+        use crate::{to_file, to_term, Defn, ParserResult};
+
+        #[test]
+        fn already_ordered() -> ParserResult<()> {
+            let input = "a := x; b := a; c := fn x => x; d := b c; main := d";
+            let mut file = to_file(input)?;
+            assert_eq!(file.topologically_order_defns(), Ok(()));
+            assert_eq!(file, to_file(input)?);
+            Ok(())
+        }
+
+        #[test]
+        fn reversed() -> ParserResult<()> {
+            let mut file = to_file("c := b a; b := a; a := fn x => x; main := c")?;
+            assert_eq!(file.topologically_order_defns(), Ok(()));
+            assert_eq!(
+                file,
+                to_file("a := fn x => x; b := a; c := b a; main := c")?
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn shadowed_refs_are_not_deps() -> ParserResult<()> {
+            let input = "f := fn g => g; g := fn x => x; main := f g";
+            let mut file = to_file(input)?;
+            assert_eq!(file.topologically_order_defns(), Ok(()));
+            assert_eq!(file, to_file(input)?);
+            Ok(())
+        }
+
+        #[test]
+        /// References to a redefined name mean the defn before them, so this is already in order.
+        fn redefined_in_order() -> ParserResult<()> {
+            let input = "a := fn x => fn y => x; b := a; a := fn x => fn y => y; main := b";
+            let mut file = to_file(input)?;
+            assert_eq!(file.topologically_order_defns(), Ok(()));
+            assert_structural_eq!(
+                file.to_unrolled().reduce(false),
+                to_term("fn x => fn y => x")?
+            );
+            assert_eq!(file, to_file(input)?);
+            Ok(())
+        }
+
+        #[test]
+        /// Forward references to a redefined name mean the last defn, and backward ones the defn
+        /// before them, wherever they end up.
+        fn redefined_out_of_order() -> ParserResult<()> {
+            let mut file = to_file(
+                "b := a; a := fn x => x; c := a; a := fn x => fn y => x; main := fn z => c b a",
+            )?;
+            assert_eq!(file.topologically_order_defns(), Ok(()));
+            let names: Vec<_> = file.defns().iter().map(Defn::name).collect();
+            assert_eq!(names, vec!["a", "c", "a", "b"]);
+            assert_eq!(
+                file.to_unrolled().reduce(false),
+                to_term("fn z => (fn x => x) (fn x => fn y => x) (fn x => fn y => x)")?
+                    .reduce(false)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn recursive() -> ParserResult<()> {
+            let input = "ok := fn x => x; even := fn n => odd n; odd := fn n => even n;
+                loop := loop; main := ok";
+            let mut file = to_file(input)?;
+            let mut err = file
+                .topologically_order_defns()
+                .expect_err("defns are recursive");
+            err.sort();
+            assert_eq!(err, vec!["even", "loop", "odd"]);
+            assert_eq!(file, to_file(input)?);
+            Ok(())
+        }
+    }
+
+    mod lint {
+        use crate::{to_file, ParserResult};
+
+        #[test]
+        fn clean() -> ParserResult<()> {
+            let file = to_file("id := fn x => x; two := id id; main := two")?;
+            assert!(file.lint().is_empty());
+            Ok(())
+        }
+
+        #[test]
+        /// Each redefinition is reported against the one it shadows.
+        fn redefined_twice() -> ParserResult<()> {
+            let file = to_file("a := x; b := a; a := y; a := z; main := a")?;
+            assert_eq!(
+                file.lint(),
+                [
+                    "`a` is defined again as defn 3, shadowing defn 1",
+                    "`a` is defined again as defn 4, shadowing defn 3",
+                ]
+            );
+            Ok(())
+        }
+    }
+
+    mod validate {
+        use crate::{to_term, ParserResult, Term};
+
+        #[test]
+        fn parsed() -> ParserResult<()> {
+            assert_eq!(to_term("fn x' => (fn y => x' y) z_1")?.validate(), Ok(()));
+            Ok(())
+        }
+
+        #[test]
+        fn bad_var() {
+            let term = Term::apply_all("f".into(), vec!["x.5".into()]);
+            assert_eq!(
+                term.validate(),
+                Err("`x.5` contains a `.`, so it could collide with a fresh name".to_string())
+            );
+        }
+
+        #[test]
+        fn bad_param() {
+            assert!(Term::lambda(&["x", "y.1"], "x".into()).validate().is_err());
+        }
+
+        #[test]
+        /// Reduction's own fresh names don't pass.
+        fn reduced() -> ParserResult<()> {
+            let term = to_term("(fn x => fn y => x y) y")?;
+            assert!(term.validate().is_ok());
+            assert!(term.reduce(false).validate().is_err());
+            Ok(())
+        }
+    }
+
+    mod file_builder {
+        use crate::{to_file, to_term, Defn, File, FileBuilder, ParserResult};
+
+        #[test]
+        fn matches_manual() -> ParserResult<()> {
+            let id = to_term("fn x => x")?;
+            let k = to_term("fn x => fn y => x")?;
+            let main = to_term("k id z")?;
+            let built = FileBuilder::new()
+                .defn("id", id.clone())
+                .defn("k", k.clone())
+                .main(main.clone());
+            let manual = File::new(
+                vec![
+                    Defn::new("id".to_string(), id),
+                    Defn::new("k".to_string(), k),
+                ],
+                main,
+            );
+            assert_structural_eq!(built.unroll(), manual.unroll());
+            Ok(())
+        }
+
+        #[test]
+        fn collect_and_extend() -> ParserResult<()> {
+            let source = to_file("a := x; b := y; c := a b; main := c")?;
+            let mut builder: FileBuilder = source.defns()[..2].iter().cloned().collect();
+            builder.extend(source.defns()[2..].iter().cloned());
+            assert_eq!(builder.main(source.main().clone()), source);
+            Ok(())
+        }
+
+        #[test]
+        /// Extending a file puts the new defns last, so they shadow the old ones.
+        fn extend_file() -> ParserResult<()> {
+            let mut file = to_file("a := x; main := a")?;
+            file.extend([Defn::new("a".to_string(), "y".into())]);
+            assert_eq!(file.unroll().reduce(false), "y".into());
+            Ok(())
+        }
+    }
+
+    mod with_library {
+        use crate::{to_file, to_library, ParserResult};
+
+        #[test]
+        fn prepends() -> ParserResult<()> {
+            let lib = to_library("id := fn x => x; k := fn x => fn y => x;")?;
+            let file = to_file("two := k id; main := two")?.with_library(lib);
+            let expected =
+                to_file("id := fn x => x; k := fn x => fn y => x; two := k id; main := two")?;
+            assert_eq!(file, expected);
+            Ok(())
+        }
+
+        #[test]
+        /// The file's defns shadow the library's.
+        fn file_wins() -> ParserResult<()> {
+            let lib = to_library("x := a;")?;
+            let file = to_file("x := b; main := x")?.with_library(lib);
+            assert_eq!(file.unroll().reduce(false), "b".into());
+            Ok(())
+        }
+    }
+
+    mod unroll_recursive {
+        use super::*;
+        use crate::{to_file, ParserResult};
+
+        const PRELUDE: &str = "\
+            true := fn t => fn e => t;
+            false := fn t => fn e => e;
+            is_zero := fn n => n (fn x => false) true;
+            pred := fn n => fn f => fn a => n (fn g => fn h => h (g f)) (fn u => a) (fn u => u);
+            mul := fn m => fn n => fn f => m (n f);
+        ";
+
+        #[test]
+        fn factorial() -> ParserResult<()> {
+            let input = PRELUDE.to_string()
+                + "fac := fn n => is_zero n (fn f => fn a => f a) (mul n (fac (pred n)));
+                   main := fac ";
+            let input = input + &format!("({});", Term::from(3));
+            let got = to_file(&input)?.unroll_recursive().reduce(false);
+            assert_eq!(usize::try_from(&got).ok(), Some(6));
+            Ok(())
+        }
+
+        #[test]
+        fn mutual_recursion() -> ParserResult<()> {
+            let input = PRELUDE.to_string()
+                + "even := fn n => is_zero n true (odd (pred n));
+                   odd := fn n => is_zero n false (even (pred n));";
+            let three = format!("({});", Term::from(3));
+            let even = to_file(&(input.clone() + "main := even " + &three))?;
+            let got = even.unroll_recursive().reduce(false);
+            assert_eq!(bool::try_from(&got).ok(), Some(false));
+            let odd = to_file(&(input + "main := odd " + &three))?;
+            let got = odd.unroll_recursive().reduce(false);
+            assert_eq!(bool::try_from(&got).ok(), Some(true));
+            Ok(())
+        }
+
+        #[test]
+        fn forward_reference() -> ParserResult<()> {
+            let input = "\
+                two := succ one;
+                one := succ zero;
+                succ := fn n => fn f => fn a => f (n f a);
+                zero := fn f => fn a => a;
+                main := two;
+            ";
+            let got = to_file(input)?.unroll_recursive().reduce(false);
+            assert_eq!(usize::try_from(&got).ok(), Some(2));
+            Ok(())
+        }
+
+        #[test]
+        /// Files that `unroll` can handle should mean the same thing under `unroll_recursive`.
+        fn agrees_with_unroll() -> ParserResult<()> {
+            let input = PRELUDE.to_string() + "main := mul (pred (fn f => fn a => f (f a))) false;";
+            let plain = to_file(&input)?.unroll().reduce(false);
+            let recursive = to_file(&input)?.unroll_recursive().reduce(false);
+            assert!(plain.alpha_equiv(&recursive));
+            Ok(())
+        }
+
+        #[test]
+        fn strongly_connected_components() {
+            // 0 -> 1 <-> 2, 3 -> 3
+            let edges = vec![vec![1], vec![2], vec![1], vec![3]];
+            assert_eq!(
+                strongly_connected(&edges),
+                vec![vec![1, 2], vec![0], vec![3]]
+            );
+        }
+    }
+
+    macro_rules! file_reduce_tests { ($($name:ident: $input:expr)*) => {
+        mod file_reduce {
+            use crate::{to_file, ParserResult};
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let cached = to_file($input)?.reduce(1000);
+                assert!(cached.alpha_equiv(&to_file($input)?.unroll().reduce(false)));
+                Ok(())
+            }
+            )*
+        }
+    }}
+
+    file_reduce_tests! {
+        reused: "succ := fn n => fn f => fn a => f (n f a); two := succ (succ (fn f => fn a => a));
+            main := two two two"
+        shadowed: "x := fn a => a; y := x; x := fn a => fn b => b; main := x y"
+        forward_reference: "a := b c; b := fn x => fn y => y; main := a"
+        // `c`'s value is the free `b` from `a`, which the later `b` mustn't be substituted into
+        forward_reference_through_value: "a := b; b := fn x => x; c := a; main := c;"
+        // `yc` has no normal form, so it's substituted as written
+        no_normal_form: "yc := fn f => (fn x => f (x x)) (fn x => f (x x));
+            main := yc (fn f => fn n => n)"
+        capture: "k := fn x => fn y => x; main := fn y => k y"
+        fibrec: include_str!("../examples/fibrec.m3lc")
+    }
 }
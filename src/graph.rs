@@ -0,0 +1,133 @@
+//! Explore every way a term can reduce.
+use alloc::collections::{BTreeMap, VecDeque};
+
+use crate::grammar::Term;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+/// The terms reachable from a term by beta reduction, and the single steps between them.
+///
+/// Alpha-equivalent terms are the same node. Nodes are numbered in breadth-first order, so the
+/// starting term is node `0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReductionGraph {
+    nodes: Vec<Term>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl ReductionGraph {
+    /// Get the terms in the graph.
+    #[must_use]
+    pub fn nodes(&self) -> &[Term] {
+        self.nodes.as_ref()
+    }
+
+    /// Get the single reduction steps in the graph, as pairs of indices into `nodes`.
+    ///
+    /// If several redexes in a term reduce to the same term, there's only one edge.
+    #[must_use]
+    pub fn edges(&self) -> &[(usize, usize)] {
+        self.edges.as_ref()
+    }
+
+    /// Get the nodes in normal form.
+    ///
+    /// By confluence there's at most one of these, but there can be none, e.g. if the depth
+    /// bound cut the graph off before reaching it.
+    pub fn normal_forms(&self) -> impl Iterator<Item = &Term> {
+        self.nodes.iter().filter(|term| !term.contains_redex())
+    }
+}
+
+impl Term {
+    /// Find every term reachable by up to `max_depth` beta reduction steps, in any order.
+    ///
+    /// From each term, this contracts each of its redexes separately, so for terms with lots of
+    /// redexes the graph gets big fast.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let graph = to_term("(fn x => f x) ((fn y => y) z)")?.reduction_graph(5);
+    /// // contracting either redex first leads to `f z`
+    /// assert_eq!(graph.nodes().len(), 4);
+    /// assert_eq!(graph.normal_forms().collect::<Vec<_>>(), vec![&to_term("f z")?]);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn reduction_graph(self, max_depth: usize) -> ReductionGraph {
+        // terms are ordered up to alpha-equivalence, so this dedupes alpha-equivalent terms
+        let mut seen = BTreeMap::from([(self.clone(), 0)]);
+        let mut graph = ReductionGraph {
+            nodes: vec![self],
+            edges: vec![],
+        };
+        let mut queue = VecDeque::from([(0, 0)]);
+
+        while let Some((from, depth)) = queue.pop_front() {
+            if depth == max_depth {
+                continue;
+            }
+            for path in graph.nodes[from].redex_paths() {
+                let mut reduct = graph.nodes[from].clone();
+                reduct.contract_at(&path);
+
+                let to = *seen.entry(reduct.clone()).or_insert_with(|| {
+                    graph.nodes.push(reduct);
+                    queue.push_back((graph.nodes.len() - 1, depth + 1));
+                    graph.nodes.len() - 1
+                });
+                if !graph.edges.contains(&(from, to)) {
+                    graph.edges.push((from, to));
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{to_term, ParserResult, Term};
+
+    #[test]
+    fn diamond() -> ParserResult<()> {
+        let graph = to_term("(fn x => f x) ((fn y => y) z)")?.reduction_graph(5);
+        assert_eq!(graph.nodes().len(), 4);
+        assert_eq!(graph.edges(), &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        assert_eq!(graph.nodes()[3], to_term("f z")?);
+        Ok(())
+    }
+
+    #[test]
+    fn depth_bound() -> ParserResult<()> {
+        let graph = to_term("(fn x => f x) ((fn y => y) z)")?.reduction_graph(1);
+        assert_eq!(graph.nodes().len(), 3);
+        assert_eq!(graph.normal_forms().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    /// Omega reduces to itself, so it's one node with a loop.
+    fn omega() -> ParserResult<()> {
+        let graph = to_term("(fn x => x x) (fn x => x x)")?.reduction_graph(10);
+        assert_eq!(graph.nodes().len(), 1);
+        assert_eq!(graph.edges(), &[(0, 0)]);
+        Ok(())
+    }
+
+    #[test]
+    /// Every path that terminates ends at the same normal form.
+    fn confluence() -> ParserResult<()> {
+        let term = to_term("(fn f => fn a => f (f a)) ((fn x => x) g) ((fn y => y) b)")?;
+        let normal_form = term.clone().reduce(false);
+        let graph = term.reduction_graph(20);
+        let normal_forms: Vec<&Term> = graph.normal_forms().collect();
+        assert_eq!(normal_forms.len(), 1);
+        assert!(normal_forms[0].alpha_equiv(&normal_form));
+        Ok(())
+    }
+}
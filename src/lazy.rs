@@ -0,0 +1,344 @@
+//! Call-by-need reduction, which shares the work of reducing an argument between its uses.
+//!
+//! `Term::reduce` substitutes a copy of the argument for each occurrence of the param, so an
+//! argument that's used twice gets reduced twice. Here, arguments aren't substituted at all:
+//! they're bound in an environment to a thunk, which is reduced the first time it's needed and
+//! then updated with the result, so every other use gets the result for free.
+//!
+//! Thunks are only reduced to weak head normal form (a lambda, or a free var applied to some
+//! arguments), which is all evaluation needs. To get the full normal form, we read the value
+//! back into a term: under a lambda, by evaluating its body with the param bound to a fresh var,
+//! and in an application of a free var, by normalizing each argument. Each thunk's normal form is
+//! cached too, so shared arguments are only read back once.
+use alloc::{collections::BTreeSet, rc::Rc};
+use core::cell::RefCell;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{grammar::Term, reduce::get_fresh_ident};
+
+/// The bindings in scope, innermost first.
+///
+/// Closures capture the environment they were made in, so it's a persistent list: extending it
+/// doesn't disturb anything else holding the old one.
+#[derive(Clone)]
+struct Env<'a>(Option<Rc<Binding<'a>>>);
+
+struct Binding<'a> {
+    name: &'a str,
+    thunk: Thunk<'a>,
+    rest: Env<'a>,
+}
+
+impl<'a> Env<'a> {
+    const fn empty() -> Self {
+        Self(None)
+    }
+
+    fn bind(&self, name: &'a str, thunk: Thunk<'a>) -> Self {
+        Self(Some(Rc::new(Binding {
+            name,
+            thunk,
+            rest: self.clone(),
+        })))
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Thunk<'a>> {
+        let mut env = self;
+        while let Some(binding) = &env.0 {
+            if binding.name == name {
+                return Some(&binding.thunk);
+            }
+            env = &binding.rest;
+        }
+        None
+    }
+}
+
+/// A term in weak head normal form.
+#[derive(Clone)]
+enum Value<'a> {
+    /// A lambda, with the environment it was made in.
+    Closure {
+        param: &'a str,
+        rule: &'a Term,
+        env: Env<'a>,
+    },
+    /// A var that isn't bound to a thunk, applied to some arguments.
+    Neutral { head: String, args: Vec<Thunk<'a>> },
+}
+
+/// A shared, updatable argument.
+type Thunk<'a> = Rc<ThunkCell<'a>>;
+
+struct ThunkCell<'a> {
+    state: RefCell<ThunkState<'a>>,
+    /// The thunk's normal form, once something has read it back.
+    normal: RefCell<Option<Term>>,
+}
+
+enum ThunkState<'a> {
+    Delayed(&'a Term, Env<'a>),
+    /// Only while the thunk is being evaluated. Thunks are only bound in environments made after
+    /// them, so a thunk can't need its own value.
+    Evaluating,
+    Forced(Value<'a>),
+}
+
+fn thunk(state: ThunkState<'_>) -> Thunk<'_> {
+    Rc::new(ThunkCell {
+        state: RefCell::new(state),
+        normal: RefCell::new(None),
+    })
+}
+
+/// Make a thunk for an argument.
+///
+/// An argument that's just a bound var is the thunk it's bound to, so everything that uses the
+/// var shares its normal form, not just its value.
+fn delay<'a>(term: &'a Term, env: &Env<'a>) -> Thunk<'a> {
+    match term {
+        Term::Var(x) => env
+            .lookup(x)
+            .map_or_else(|| thunk(ThunkState::Delayed(term, env.clone())), Rc::clone),
+        _ => thunk(ThunkState::Delayed(term, env.clone())),
+    }
+}
+
+/// The state of a call-by-need reduction.
+struct Machine<'a> {
+    /// The free vars of the term being reduced, which binders in the output can't be named.
+    free: BTreeSet<&'a str>,
+    /// How many beta steps we've taken.
+    steps: usize,
+}
+
+impl<'a> Machine<'a> {
+    /// Evaluate `term` to weak head normal form.
+    ///
+    /// This is a Krivine machine: the arguments of the applications we've gone into the left
+    /// side of wait on a stack, and each lambda we reach pops one off and binds it. The only
+    /// recursion is into thunks.
+    fn eval(&mut self, mut term: &'a Term, mut env: Env<'a>) -> Value<'a> {
+        // the last argument pushed is the first one to apply
+        let mut args: Vec<Thunk<'a>> = vec![];
+        loop {
+            let value = match term {
+                Term::Appl { left, right } => {
+                    args.push(delay(right, &env));
+                    term = left;
+                    continue;
+                }
+                Term::Lam { param, rule } => Value::Closure {
+                    param,
+                    rule,
+                    env: env.clone(),
+                },
+                Term::Var(x) => match env.lookup(x) {
+                    Some(thunk) => self.force(&Rc::clone(thunk)),
+                    None => Value::Neutral {
+                        head: x.clone(),
+                        args: vec![],
+                    },
+                },
+            };
+            match value {
+                Value::Closure {
+                    param,
+                    rule,
+                    env: closure_env,
+                } => {
+                    let Some(arg) = args.pop() else {
+                        return Value::Closure {
+                            param,
+                            rule,
+                            env: closure_env,
+                        };
+                    };
+                    self.steps += 1;
+                    env = closure_env.bind(param, arg);
+                    term = rule;
+                }
+                Value::Neutral {
+                    head,
+                    args: mut applied,
+                } => {
+                    applied.extend(args.into_iter().rev());
+                    return Value::Neutral {
+                        head,
+                        args: applied,
+                    };
+                }
+            }
+        }
+    }
+
+    /// Evaluate a thunk, or get the value it was already evaluated to.
+    fn force(&mut self, thunk: &Thunk<'a>) -> Value<'a> {
+        let state = thunk.state.replace(ThunkState::Evaluating);
+        let value = match state {
+            ThunkState::Forced(value) => value,
+            ThunkState::Delayed(term, env) => self.eval(term, env),
+            ThunkState::Evaluating => unreachable!("thunks never need their own value"),
+        };
+        *thunk.state.borrow_mut() = ThunkState::Forced(value.clone());
+        value
+    }
+
+    /// Read a value back into a term in normal form.
+    ///
+    /// `scope` is the names of the binders we're under, which binders inside can't reuse, or
+    /// they'd capture them.
+    fn read_back(&mut self, value: Value<'a>, scope: &mut Vec<String>) -> Term {
+        match value {
+            Value::Closure { param, rule, env } => {
+                let name = if scope.iter().any(|x| x == param) || self.free.contains(param) {
+                    get_fresh_ident(param)
+                } else {
+                    param.to_string()
+                };
+                let var = thunk(ThunkState::Forced(Value::Neutral {
+                    head: name.clone(),
+                    args: vec![],
+                }));
+                let body = self.eval(rule, env.bind(param, var));
+                scope.push(name);
+                let rule = self.read_back(body, scope);
+                let param = scope.pop().expect("pushed above");
+                Term::Lam {
+                    param,
+                    rule: rule.into(),
+                }
+            }
+            Value::Neutral { head, args } => Term::apply_all(
+                head.into(),
+                args.iter().map(|arg| self.normalize(arg, scope)).collect(),
+            ),
+        }
+    }
+
+    /// Get a thunk's normal form, reading it back if nothing has yet.
+    ///
+    /// The cached normal form is safe to reuse anywhere the thunk is: its free vars were all in
+    /// scope when it was read back, so its binders avoid them.
+    fn normalize(&mut self, thunk: &Thunk<'a>, scope: &mut Vec<String>) -> Term {
+        if let Some(normal) = thunk.normal.borrow().as_ref() {
+            return normal.clone();
+        }
+        let value = self.force(thunk);
+        let normal = self.read_back(value, scope);
+        *thunk.normal.borrow_mut() = Some(normal.clone());
+        normal
+    }
+}
+
+impl Term {
+    /// Perform call-by-need beta reduction, returning the normal form and how many steps it took.
+    ///
+    /// This gives the same normal form as `reduce`, up to alpha-equivalence, but each argument is
+    /// reduced at most once however many times it's used, so it can take far fewer steps. Like
+    /// normal order, arguments that aren't used are never reduced at all. Binders keep their
+    /// names where that doesn't capture anything, and otherwise get fresh ones.
+    ///
+    /// # Safety
+    /// The halting problem is a thing. Ergo, this can cause unhandled infinite regress.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// // the argument is reduced once, and the result used twice
+    /// let term = to_term("(fn x => f x x) ((fn n => n n) (fn y => y))")?;
+    /// let (normal, steps) = term.reduce_lazy();
+    /// assert_eq!(normal, to_term("f (fn y => y) (fn y => y)")?);
+    /// assert_eq!(steps, 3);
+    /// assert_eq!(term.reduce_stats(100).1.steps, 5);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn reduce_lazy(&self) -> (Self, usize) {
+        let mut machine = Machine {
+            free: self.free_vars(),
+            steps: 0,
+        };
+        let value = machine.eval(self, Env::empty());
+        let normal = machine.read_back(value, &mut vec![]);
+        (normal, machine.steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+
+    macro_rules! agrees_with_reduce_tests { ($($name:ident: $input:expr)*) => {
+        mod agrees_with_reduce {
+            use super::*;
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let term = to_term($input)?;
+                let (lazy, lazy_steps) = term.reduce_lazy();
+                let (normal, stats) = term.reduce_stats(usize::MAX);
+                assert_eq!(lazy, normal.expect("the test terms all terminate"));
+                assert!(lazy_steps <= stats.steps);
+                Ok(())
+            }
+            )*
+        }
+    }}
+
+    agrees_with_reduce_tests! {
+        var: "x"
+        normal: "fn x => x y"
+        nested_sub: "(fn f => fn a => f) x"
+        many_renames: "(fn f => fn y => fn x => x (y f)) y x f"
+        under_lambda: "fn y => (fn x => x) y"
+        lazy_eval: "(fn t => fn e => t) x ((fn x => x x)(fn x => x x))"
+        y_combinator: "(fn g => ((fn y => g (y y)) (fn y => g (y y))))
+            (fn f => fn x => x q (f (fn t => fn e => t))) (fn t => fn e => e)"
+        two_two: "(fn f => fn a => f (f a)) (fn f => fn a => f (f a))"
+        fibbit: "(fn n => (fn p => p (fn t => fn e => t)) (n (fn p => (fn a => fn b => fn s => s a b) ((fn p => p (fn t => fn e => e)) p) ((fn m => fn n => m (fn n => fn f => fn x => f (n f x)) n) ((fn p => p (fn t => fn e => t)) p) ((fn p => p (fn t => fn e => e)) p))) ((fn a => fn b => fn s => s a b) (fn f => fn x => x) ((fn n => fn f => fn x => f (n f x)) (fn f => fn x => x))))) (fn f => fn x => f (f (f (f (f (f (f (f (f (f x))))))))))"
+    }
+
+    #[test]
+    /// Binders that would capture a free var, or an outer binder, get renamed.
+    fn avoids_capture() -> ParserResult<()> {
+        let term = to_term("(fn x => fn y => x y) y")?;
+        let (normal, _) = term.reduce_lazy();
+        assert_eq!(normal, to_term("fn z => y z")?);
+        let (normal, _) = to_term("(fn f => fn x => f x) (fn y => fn x => y x)")?.reduce_lazy();
+        assert_eq!(normal, to_term("fn a => fn b => a b")?);
+        Ok(())
+    }
+
+    #[test]
+    /// A shared argument is only reduced once, where `reduce` reduces each copy.
+    fn shares_work() {
+        let arg = Term::apply_all(2.into(), vec![2.into()]);
+        let term = Term::apply_all(
+            Term::lambda(&["x"], Term::apply_all("f".into(), vec!["x".into(); 4])),
+            vec![arg],
+        );
+        let (lazy, lazy_steps) = term.reduce_lazy();
+        let (normal, stats) = term.reduce_stats(usize::MAX);
+        assert_eq!(Some(lazy), normal);
+        assert!(
+            lazy_steps * 3 < stats.steps,
+            "{} {}",
+            lazy_steps,
+            stats.steps
+        );
+    }
+
+    #[test]
+    fn keeps_names() -> ParserResult<()> {
+        let (normal, _) =
+            to_term("(fn n => fn f => fn a => f (n f a)) (fn f => fn a => a)")?.reduce_lazy();
+        assert_structural_eq!(normal, to_term("fn f => fn a => f a")?);
+        Ok(())
+    }
+}
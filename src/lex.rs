@@ -0,0 +1,188 @@
+//! A flat token stream, for tooling like syntax highlighters.
+//!
+//! This doesn't run the parser, so it works on files that don't parse, as long as every
+//! character is part of some token. The token rules match the terminals in `m3lc.pest`.
+use std::ops::Range;
+
+use pest::{error::ErrorVariant, Span};
+use pest_consume::Error;
+
+use crate::parse::ParserResult;
+
+/// The kinds of token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// `fn`, `main`, `letrec`, `in`, or an `include` before a string.
+    Keyword,
+    Ident,
+    /// A lone `_` param.
+    Wildcard,
+    /// `=>`
+    Arrow,
+    /// `:=`
+    Define,
+    /// The `:` before an arity annotation.
+    Colon,
+    LParen,
+    RParen,
+    Semicolon,
+    /// A quoted include path, quotes and all.
+    String,
+    /// A `#` comment, up to the end of the line.
+    Comment,
+}
+
+/// A token and where it is in the input, as a byte range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+const KEYWORDS: [&str; 4] = ["fn", "main", "letrec", "in"];
+
+/// Whether `c` can be part of an ident, not counting the primes at the end.
+const fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Split the input into tokens, skipping whitespace.
+///
+/// # Errors
+/// Returns an error pointing at the first character that can't start a token, or at an
+/// unterminated string.
+///
+/// # Example
+/// ```
+/// # use m3lc::{tokenize, TokenKind};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #
+/// let kinds: Vec<_> = tokenize("id := fn x => x;")?.into_iter().map(|t| t.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     vec![
+///         TokenKind::Ident,
+///         TokenKind::Define,
+///         TokenKind::Keyword,
+///         TokenKind::Ident,
+///         TokenKind::Arrow,
+///         TokenKind::Ident,
+///         TokenKind::Semicolon,
+///     ]
+/// );
+/// #
+/// # Ok(())}
+/// ```
+pub fn tokenize(input: &str) -> ParserResult<Vec<Token>> {
+    let error = |message: &str, start: usize, end: usize| {
+        let span = Span::new(input, start, end).expect("span came from input");
+        Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: message.to_string(),
+            },
+            span,
+        )
+    };
+
+    let mut tokens = vec![];
+    let mut start = 0;
+    while let Some(c) = input[start..].chars().next() {
+        let rest = &input[start..];
+        // the kind and length of the token starting here
+        let (kind, len) = match c {
+            c if c.is_whitespace() => {
+                start += c.len_utf8();
+                continue;
+            }
+            '#' => (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len())),
+            '(' => (TokenKind::LParen, 1),
+            ')' => (TokenKind::RParen, 1),
+            ';' => (TokenKind::Semicolon, 1),
+            _ if rest.starts_with("=>") => (TokenKind::Arrow, 2),
+            _ if rest.starts_with(":=") => (TokenKind::Define, 2),
+            ':' => (TokenKind::Colon, 1),
+            '"' => match rest[1..].find('"') {
+                Some(end) => (TokenKind::String, end + 2),
+                None => return Err(error("unterminated string", start, input.len())),
+            },
+            c if is_ident_char(c) => {
+                let chars = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+                let primes = rest[chars..]
+                    .find(|c| c != '\'')
+                    .unwrap_or(rest.len() - chars);
+                let len = chars + primes;
+                let word = &rest[..len];
+                let kind = if word == "_" {
+                    TokenKind::Wildcard
+                } else if KEYWORDS.contains(&word)
+                    // `include` is only special at the start of an include statement
+                    || (word == "include" && rest[len..].trim_start().starts_with('"'))
+                {
+                    TokenKind::Keyword
+                } else {
+                    TokenKind::Ident
+                };
+                (kind, len)
+            }
+            c => return Err(error("unexpected character", start, start + c.len_utf8())),
+        };
+        tokens.push(Token {
+            kind,
+            span: start..start + len,
+        });
+        start += len;
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TokenKind::*;
+
+    macro_rules! tokenize_tests { ($($name:ident: $input:expr, $expected:expr)*) => {
+        mod tokens {
+            use super::*;
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let input = $input;
+                let tokens: Vec<_> = tokenize(input)?
+                    .into_iter()
+                    .map(|t| (t.kind, &input[t.span]))
+                    .collect();
+                assert_eq!(tokens, $expected);
+                Ok(())
+            }
+            )*
+        }
+    }}
+
+    tokenize_tests! {
+        empty: "  \n ", vec![]
+        lam: "fn x => x", vec![(Keyword, "fn"), (Ident, "x"), (Arrow, "=>"), (Ident, "x")]
+        wildcard: "fn _ => _x", vec![(Keyword, "fn"), (Wildcard, "_"), (Arrow, "=>"), (Ident, "_x")]
+        keyword_prefix: "fnord main_ inc", vec![(Ident, "fnord"), (Ident, "main_"), (Ident, "inc")]
+        primes: "f'' fn' x'", vec![(Ident, "f''"), (Ident, "fn'"), (Ident, "x'")]
+        parens: "(f)(g)", vec![(LParen, "("), (Ident, "f"), (RParen, ")"), (LParen, "("), (Ident, "g"), (RParen, ")")]
+        defn: "main := x;;", vec![(Keyword, "main"), (Define, ":="), (Ident, "x"), (Semicolon, ";"), (Semicolon, ";")]
+        comment: "x # fn x => x\ny", vec![(Ident, "x"), (Comment, "# fn x => x"), (Ident, "y")]
+        include: "include \"lib.m3lc\";", vec![(Keyword, "include"), (String, "\"lib.m3lc\""), (Semicolon, ";")]
+        include_as_ident: "include := x", vec![(Ident, "include"), (Define, ":="), (Ident, "x")]
+        arity: "k : 2 := x", vec![(Ident, "k"), (Colon, ":"), (Ident, "2"), (Define, ":="), (Ident, "x")]
+        letrec: "letrec f := f in f", vec![(Keyword, "letrec"), (Ident, "f"), (Define, ":="), (Ident, "f"), (Keyword, "in"), (Ident, "f")]
+        // lexes fine even though it doesn't parse
+        invalid: "fn => ) :=", vec![(Keyword, "fn"), (Arrow, "=>"), (RParen, ")"), (Define, ":=")]
+    }
+
+    #[test]
+    fn unexpected_character() {
+        assert!(tokenize("fn x => x + y").is_err());
+        assert!(tokenize("x = y").is_err());
+    }
+
+    #[test]
+    fn unterminated_string() {
+        assert!(tokenize("include \"lib.m3lc;").is_err());
+    }
+}
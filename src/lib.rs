@@ -1,12 +1,69 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![feature(box_patterns, box_syntax, test)]
+extern crate alloc;
+
+/// Assert that two terms, or defns, files, or libraries, are structurally equal, since
+/// `assert_eq!` only checks alpha-equivalence.
+#[cfg(test)]
+macro_rules! assert_structural_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        let (left, right) = (&$left, &$right);
+        assert!(
+            left.structural_eq(right),
+            "not structurally equal\n  left: `{}`\n right: `{}`",
+            left,
+            right
+        );
+    }};
+}
+
+#[cfg(any(test, feature = "proptest"))]
+mod arbitrary;
+#[cfg(feature = "std")]
 mod cli;
 mod data;
+mod diff;
 mod grammar;
+mod graph;
+mod lazy;
+#[cfg(feature = "std")]
+mod lex;
+#[cfg(feature = "std")]
 mod parse;
+mod path;
+#[cfg(not(feature = "std"))]
+mod prelude;
 mod reduce;
+mod shared;
+mod vars;
+mod visit;
+
+// `alloc` has no hash maps, so without `std`, we make do with ordered ones. These are only for
+// use inside the crate: public signatures always use the ordered ones, so that turning `std` on or
+// off doesn't change the API.
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap as Map, BTreeSet as Set};
+#[cfg(feature = "std")]
+use std::collections::{HashMap as Map, HashSet as Set};
 
+#[cfg(feature = "proptest")]
+pub use arbitrary::TermParams;
+#[cfg(feature = "std")]
 pub use cli::run;
 pub use data::{bool, church};
-pub use grammar::{Defn, File, Term};
+pub use diff::TermDiff;
+pub use grammar::{Defn, File, FileBuilder, LambdaStyle, Library, Term};
+pub use graph::ReductionGraph;
+#[cfg(feature = "std")]
+pub use lex::{tokenize, Token, TokenKind};
 // TODO: we should expose our own error type
-pub use parse::{to_file, to_term, ParserResult};
+#[cfg(feature = "std")]
+pub use parse::{
+    lint_source, reduce_source, to_file, to_file_in_dir, to_library, to_term, ParserError,
+    ParserResult,
+};
+pub use path::Direction;
+pub use reduce::{reduce_all, ReduceStats, ReductionOutcome};
+pub use shared::SharedTerm;
+pub use vars::CleanNames;
+pub use visit::Visitor;
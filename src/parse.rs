@@ -1,19 +1,48 @@
 //! Parse a .m3lc file.
-use crate::grammar::{Defn, File, Term};
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, BufRead},
+    mem,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    data::combinator::Y,
+    grammar::{shadowed_defns, Defn, DefnValues, File, Library, Term},
+};
 use Term::{Appl, Lam};
 
-use pest::prec_climber as pcl;
+use pest::{error::ErrorVariant, prec_climber as pcl, Position, Span};
 use pest_consume::{match_nodes, Error, Parser};
 
 #[derive(Parser)]
 #[grammar = "m3lc.pest"]
 pub struct M3LCParser;
 
+/// A Pest parsing error.
+pub type ParserError = Error<Rule>;
+
 /// A Result alias for Pest parsing errors.
-pub type ParserResult<T> = std::result::Result<T, Error<Rule>>;
+pub type ParserResult<T> = std::result::Result<T, ParserError>;
 
 type Node<'a> = pest_consume::Node<'a, Rule, ()>;
 
+/// An `include` statement, before we've read the included file.
+struct Include {
+    path: String,
+    /// Where the statement is in the including file, for error messages.
+    start: usize,
+    end: usize,
+}
+
+/// A top-level statement, for parsing a file one statement at a time.
+enum Statement {
+    Defn(Defn),
+    Main(Term),
+}
+
 lazy_static::lazy_static! {
     /// A precedence climber to impl associativity. pest_consume's macros handle most of the work.
     static ref CLIMBER: pcl::PrecClimber<Rule> = pcl::PrecClimber::new(
@@ -23,6 +52,12 @@ lazy_static::lazy_static! {
     );
 }
 
+/// The most nodes a numeric literal can expand to, which allows literals up to `0x400`.
+///
+/// A numeral is as deep as it is big, and not everything that walks a term avoids recursing, so
+/// much bigger literals could overflow the stack. They're also more likely a typo than intended.
+const MAX_LITERAL_SIZE: usize = 2 * 0x400 + 3;
+
 #[pest_consume::parser]
 impl M3LCParser {
     /// Parse an EOI.
@@ -35,6 +70,13 @@ impl M3LCParser {
         Ok(input.as_str().into())
     }
 
+    /// Parse a param to a `String`.
+    ///
+    /// param = { ident | wildcard }
+    fn param(input: Node) -> ParserResult<String> {
+        Ok(input.as_str().into())
+    }
+
     /// Parse a name to a `Term::Var`.
     ///
     /// name := ident
@@ -44,12 +86,49 @@ impl M3LCParser {
         ))
     }
 
+    /// Parse a hex literal, like `0xFF`, to a `u64`.
+    fn hex(input: Node) -> ParserResult<u64> {
+        u64::from_str_radix(&input.as_str()[2..], 16).map_err(|e| input.error(e))
+    }
+
+    /// Parse a binary literal, like `0b1010`, to a `u64`.
+    fn bin(input: Node) -> ParserResult<u64> {
+        u64::from_str_radix(&input.as_str()[2..], 2).map_err(|e| input.error(e))
+    }
+
+    /// Parse a numeric literal to a Church numeral.
+    ///
+    /// num = { hex | bin }
+    ///
+    /// Literals bigger than `MAX_LITERAL_SIZE` nodes are an error, rather than a huge term.
+    fn num(input: Node) -> ParserResult<Term> {
+        let n = match_nodes!(input.children();
+            [hex(n)] => n,
+            [bin(n)] => n,
+        );
+        Term::numeral_within(n, MAX_LITERAL_SIZE).map_err(|e| input.error(e))
+    }
+
     /// Parse a lam to a `Term::Lam`.
     ///
-    /// lam = { "fn" ~ ident ~ "=>" ~ appl }
+    /// lam = { "fn" ~ param ~ "=>" ~ appl }
     fn lam(input: Node) -> ParserResult<Term> {
         Ok(match_nodes!(input.into_children();
-            [ident(param), appl(rule)] => Lam{ param, rule: box rule },
+            [param(param), appl(rule)] => Lam{ param, rule: box rule },
+        ))
+    }
+
+    /// Parse a letrec to its desugared `Term`.
+    ///
+    /// letrec = { "letrec" ~ ident ~ ":=" ~ appl ~ "in" ~ appl }
+    ///
+    /// `letrec f := body in e` becomes `(fn f => e) (Y (fn f => body))`.
+    fn letrec(input: Node) -> ParserResult<Term> {
+        Ok(match_nodes!(input.into_children();
+            [ident(name), appl(body), appl(e)] => Term::apply_all(
+                Term::lambda(&[&name], e),
+                vec![Term::apply_all(Y.clone(), vec![Term::lambda(&[&name], body)])],
+            )
         ))
     }
 
@@ -74,19 +153,27 @@ impl M3LCParser {
 
     /// Parse a term to a `Term`.
     ///
-    /// term = { lam | var | "(" ~ appl ~ ")" }
+    /// term = { letrec | lam | num | var | "(" ~ appl ~ ")" }
     fn term(input: Node) -> ParserResult<Term> {
         Ok(match_nodes!(input.into_children();
             [appl(a)] => a,
+            [letrec(l)] => l,
             [lam(l)] => l,
+            [num(n)] => n,
             [var(x)] => x
         ))
     }
 
+    /// Parse an arity annotation to a `usize`.
+    fn arity(input: Node) -> ParserResult<usize> {
+        input.as_str().parse().map_err(|e| input.error(e))
+    }
+
     /// Parse a defn to a `Defn`.
     fn defn(input: Node) -> ParserResult<Defn> {
         Ok(match_nodes!(input.into_children();
-            [ident(name), appl(term)] => Defn::new(name, term)
+            [ident(name), appl(term)] => Defn::new(name, term),
+            [ident(name), arity(arity), appl(term)] => Defn::new(name, term).with_arity(arity)
         ))
     }
 
@@ -104,10 +191,59 @@ impl M3LCParser {
         ))
     }
 
-    /// Parse a file to a `File`.
-    fn file(input: Node) -> ParserResult<File> {
+    /// Parse a string literal to its contents.
+    fn string(input: Node) -> ParserResult<String> {
         Ok(match_nodes!(input.into_children();
-            [defns(defns), main(main), EOI(_)] => File::new(defns, main)
+            [string_inner(s)] => s
+        ))
+    }
+
+    /// Parse the inside of a string literal to a `String`.
+    fn string_inner(input: Node) -> ParserResult<String> {
+        Ok(input.as_str().into())
+    }
+
+    /// Parse an include to an `Include`.
+    fn include(input: Node) -> ParserResult<Include> {
+        let span = input.as_span();
+        Ok(match_nodes!(input.into_children();
+            [string(path)] => Include { path, start: span.start(), end: span.end() }
+        ))
+    }
+
+    /// Parse includes into a `Vec<Include>`.
+    fn includes(input: Node) -> ParserResult<Vec<Include>> {
+        Ok(match_nodes!(input.into_children();
+            [include(includes)..] => includes.collect()
+        ))
+    }
+
+    /// Parse a file to a `File`, and the includes it still needs.
+    fn file(input: Node) -> ParserResult<(Vec<Include>, File)> {
+        Ok(match_nodes!(input.into_children();
+            [includes(includes), defns(defns), main(main), EOI(_)] => (includes, File::new(defns, main))
+        ))
+    }
+
+    /// Parse a library to a `Library`, and the includes it still needs.
+    fn library(input: Node) -> ParserResult<(Vec<Include>, Library)> {
+        Ok(match_nodes!(input.into_children();
+            [includes(includes), defns(defns), EOI(_)] => (includes, Library::new(defns))
+        ))
+    }
+
+    /// Parse a single defn or main.
+    fn statement(input: Node) -> ParserResult<Statement> {
+        Ok(match_nodes!(input.into_children();
+            [defn(defn), EOI(())] => Statement::Defn(defn),
+            [main(main), EOI(())] => Statement::Main(main)
+        ))
+    }
+
+    /// Parse a whole input that's just a term.
+    fn standalone_term(input: Node) -> ParserResult<Term> {
+        Ok(match_nodes!(input.into_children();
+            [appl(term), EOI(_)] => term
         ))
     }
 }
@@ -117,17 +253,369 @@ impl M3LCParser {
 /// # Errors
 /// Errors if the input is invalid M3LC code.
 pub fn to_term(input: &str) -> ParserResult<Term> {
-    M3LCParser::appl(M3LCParser::parse(Rule::appl, input)?.single()?)
+    M3LCParser::standalone_term(M3LCParser::parse(Rule::standalone_term, input)?.single()?)
 }
 
 /// Parse a str to a file.
 ///
+/// Includes are resolved relative to the current directory; see `to_file_in_dir`.
+///
 /// # Errors
-/// Errors if the input is invalid M3LC code.
+/// Errors if the input is invalid M3LC code, or an include can't be resolved.
 pub fn to_file(input: &str) -> ParserResult<File> {
+    to_file_in_dir(input, Path::new("."))
+}
+
+/// Parse a str to a file, resolving its includes relative to `dir`.
+///
+/// Each `include "path";` is read and parsed as a library, and its defns go before the
+/// including file's, as in `File::with_library`. Included files can include other files,
+/// relative to their own directory.
+///
+/// # Errors
+/// Errors if the input is invalid M3LC code, an included file can't be read or parsed, or the
+/// includes are circular.
+pub fn to_file_in_dir(input: &str, dir: &Path) -> ParserResult<File> {
+    let (includes, file) = parse_file(input)?;
+    Ok(file.with_library(resolve_includes(input, &includes, dir, &mut vec![])?))
+}
+
+/// Parse a file, without resolving its includes.
+fn parse_file(input: &str) -> ParserResult<(Vec<Include>, File)> {
     M3LCParser::file(M3LCParser::parse(Rule::file, input)?.single()?)
 }
 
+/// Look for likely mistakes in a file's source, returning a warning for each.
+///
+/// This finds what `File::lint` does, but only among the file's own defns: includes aren't
+/// resolved, since overriding an included defn is often deliberate. Defns are pointed to by
+/// their line and column in `input`.
+///
+/// # Errors
+/// Errors if the input is invalid M3LC code.
+///
+/// # Example
+/// ```
+/// # use m3lc::lint_source;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #
+/// let input = "id := fn x => x;\nk := fn x => fn y => x;\nid := k;\nmain := id";
+/// assert_eq!(
+///     lint_source(input)?,
+///     ["`id` is defined again at 3:1, shadowing the defn at 1:1"]
+/// );
+/// #
+/// # Ok(())}
+/// ```
+pub fn lint_source(input: &str) -> ParserResult<Vec<String>> {
+    let file = M3LCParser::parse(Rule::file, input)?.single()?;
+    // each defn's name, and where it starts
+    let defns: Vec<(&str, (usize, usize))> = file
+        .as_pair()
+        .clone()
+        .into_inner()
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::defn)
+        .filter_map(|defn| defn.into_inner().next())
+        .map(|name| (name.as_str(), name.as_span().start_pos().line_col()))
+        .collect();
+    Ok(shadowed_defns(defns.iter().map(|(name, _)| *name))
+        .into_iter()
+        .map(|(earlier, later)| {
+            let (name, (line, col)) = defns[later];
+            let (_, (earlier_line, earlier_col)) = defns[earlier];
+            format!(
+                "`{}` is defined again at {}:{}, shadowing the defn at {}:{}",
+                name, line, col, earlier_line, earlier_col
+            )
+        })
+        .collect())
+}
+
+/// Parse a file, unroll it, and reduce it, giving up after `max_steps` steps.
+///
+/// This is the whole pipeline behind one function that doesn't print anything or touch the
+/// filesystem, for embedding in things like a web playground. Returns the normal form with fresh
+/// names cleaned up, as the CLI prints it.
+///
+/// # Errors
+/// Returns a human-readable message if the input is invalid, has includes (since there are no
+/// files to include), or doesn't reach a normal form in time.
+///
+/// # Example
+/// ```
+/// # use m3lc::reduce_source;
+/// let output = reduce_source("id := fn x => x; main := id (fn y => y z)", 100);
+/// assert_eq!(output.as_deref(), Ok("fn y => y z"));
+/// ```
+pub fn reduce_source(input: &str, max_steps: usize) -> Result<String, String> {
+    let (includes, file) = parse_file(input).map_err(|e| e.to_string())?;
+    if let Some(include) = includes.first() {
+        let span = Span::new(input, include.start, include.end).expect("span came from input");
+        let message = "includes aren't supported here".to_string();
+        let error: ParserError = Error::new_from_span(ErrorVariant::CustomError { message }, span);
+        return Err(error.to_string());
+    }
+    file.unroll()
+        .reduce_bounded(max_steps)
+        .map(|normal| normal.strip_fresh_suffixes().to_string())
+        .map_err(|_| format!("no normal form after {} steps", max_steps))
+}
+
+/// Parse a str to a library, i.e. a file without a main.
+///
+/// Includes are resolved relative to the current directory.
+///
+/// # Errors
+/// Errors if the input is invalid M3LC code, or an include can't be resolved.
+pub fn to_library(input: &str) -> ParserResult<Library> {
+    to_library_impl(input, Path::new("."), &mut vec![])
+}
+
+/// Parse a library, resolving its includes relative to `dir`.
+///
+/// `visiting` is the stack of files we're in the middle of including, to catch cycles.
+fn to_library_impl(input: &str, dir: &Path, visiting: &mut Vec<PathBuf>) -> ParserResult<Library> {
+    let (includes, lib) = M3LCParser::library(M3LCParser::parse(Rule::library, input)?.single()?)?;
+    let included = resolve_includes(input, &includes, dir, visiting)?;
+    Ok(Library::new(
+        included
+            .defns()
+            .iter()
+            .chain(lib.defns())
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Read and parse each include, collecting all their defns in order.
+fn resolve_includes(
+    input: &str,
+    includes: &[Include],
+    dir: &Path,
+    visiting: &mut Vec<PathBuf>,
+) -> ParserResult<Library> {
+    let mut defns = vec![];
+    for include in includes {
+        // errors point at the include statement in the including file
+        let error = |message: String| {
+            let span = Span::new(input, include.start, include.end).expect("span came from input");
+            Error::new_from_span(ErrorVariant::CustomError { message }, span)
+        };
+
+        let path = dir.join(&include.path);
+        let path = path
+            .canonicalize()
+            .map_err(|e| error(format!("can't include {}: {}", path.display(), e)))?;
+        if visiting.contains(&path) {
+            return Err(error(format!("circular include of {}", path.display())));
+        }
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| error(format!("can't include {}: {}", path.display(), e)))?;
+
+        visiting.push(path.clone());
+        let lib = to_library_impl(
+            &contents,
+            path.parent().unwrap_or_else(|| Path::new(".")),
+            visiting,
+        )
+        .map_err(|e| e.with_path(&path.display().to_string()))?;
+        visiting.pop();
+
+        defns.extend_from_slice(lib.defns());
+    }
+    Ok(Library::new(defns))
+}
+
+impl File {
+    /// Read a file one statement at a time, reducing each defn as soon as it's parsed.
+    ///
+    /// This is `File::reduce` for files too big to hold in memory as source or as one unrolled
+    /// term: each defn is normalized with the earlier defns' values substituted in, as there, and
+    /// yielded with its name. Main is optional, so libraries can be streamed too; if there is one,
+    /// it must come last, and it's reduced all the way and yielded as `main`.
+    ///
+    /// The stream stops after the first error. Includes aren't supported, and positions in parse
+    /// errors are relative to the start of the statement.
+    ///
+    /// # Errors
+    /// Each item is an error if reading fails, the statement is invalid M3LC code, or it comes
+    /// after main.
+    ///
+    /// # Safety
+    /// Like `File::reduce`, reducing main can loop forever.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{File, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let source = "succ := fn n => fn f => fn a => f (n f a);\n\
+    ///     two := succ (succ (fn f => fn a => a));\n\
+    ///     main := two two";
+    /// let values = File::stream_reduce(source.as_bytes(), 1000)
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(values[1], ("two".to_string(), Term::from(2)));
+    /// assert_eq!(values[2], ("main".to_string(), Term::from(4)));
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn stream_reduce<R: BufRead>(
+        reader: R,
+        max_defn_steps: usize,
+    ) -> impl Iterator<Item = ParserResult<(String, Term)>> {
+        StreamReduce {
+            lines: reader.lines(),
+            statements: VecDeque::new(),
+            partial: String::new(),
+            values: DefnValues::new(max_defn_steps),
+            seen_main: false,
+            done: false,
+        }
+    }
+}
+
+/// The iterator behind `File::stream_reduce`.
+struct StreamReduce<R> {
+    lines: io::Lines<R>,
+    /// Complete statements that have been read but not yet reduced.
+    statements: VecDeque<String>,
+    /// The start of a statement whose semicolon hasn't been read yet.
+    partial: String,
+    values: DefnValues,
+    seen_main: bool,
+    done: bool,
+}
+
+impl<R> StreamReduce<R> {
+    /// Parse and reduce a single statement, stopping the stream if that fails.
+    fn reduce_statement(&mut self, statement: &str) -> ParserResult<(String, Term)> {
+        let result = self.reduce_statement_impl(statement);
+        self.done |= result.is_err();
+        result
+    }
+
+    fn reduce_statement_impl(&mut self, statement: &str) -> ParserResult<(String, Term)> {
+        if self.seen_main {
+            let span = Span::new(statement, 0, statement.len()).expect("span is the whole input");
+            let message = "main must be the last statement".to_string();
+            return Err(Error::new_from_span(
+                ErrorVariant::CustomError { message },
+                span,
+            ));
+        }
+        match M3LCParser::statement(M3LCParser::parse(Rule::statement, statement)?.single()?)? {
+            Statement::Defn(defn) => Ok(self.values.push(defn).clone()),
+            Statement::Main(main) => {
+                self.seen_main = true;
+                Ok((
+                    "main".to_string(),
+                    self.values.substitute(main).reduce(false),
+                ))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StreamReduce<R> {
+    type Item = ParserResult<(String, Term)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.done {
+                return None;
+            }
+            if let Some(statement) = self.statements.pop_front() {
+                return Some(self.reduce_statement(&statement));
+            }
+            match self.lines.next() {
+                Some(Ok(line)) => {
+                    // `#` only ever starts a comment outside of includes, which we don't support,
+                    // so dropping comments here means any `;` left is a separator
+                    let code = line.split('#').next().unwrap_or_default();
+                    self.partial.push_str(code);
+                    self.partial.push('\n');
+                    while let Some(end) = self.partial.find(';') {
+                        let statement: String = self.partial.drain(..=end).collect();
+                        let statement = statement.trim_end_matches(';');
+                        // semicolons can be doubled up
+                        if !statement.trim().is_empty() {
+                            self.statements.push_back(statement.to_string());
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    self.done = true;
+                    let message = format!("can't read input: {}", e);
+                    return Some(Err(Error::new_from_pos(
+                        ErrorVariant::CustomError { message },
+                        Position::from_start(""),
+                    )));
+                }
+                None => {
+                    // main doesn't need a semicolon
+                    self.done = true;
+                    let statement = mem::take(&mut self.partial);
+                    return (!statement.trim().is_empty())
+                        .then(|| self.reduce_statement(&statement));
+                }
+            }
+        }
+    }
+}
+
+impl Term {
+    /// Check alpha-equivalence against a term written as source code.
+    ///
+    /// This is `self.alpha_equiv(&to_term(src)?)`, for comparing against a literal without
+    /// spelling out the parse.
+    ///
+    /// # Errors
+    /// Errors if `src` is invalid M3LC code.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => x) (fn y => y)")?.reduce(false);
+    /// assert!(term.equiv_str("fn x => x")?);
+    /// assert!(!term.equiv_str("fn x => y")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn equiv_str(&self, src: &str) -> ParserResult<bool> {
+        Ok(self.alpha_equiv(&to_term(src)?))
+    }
+}
+
+impl FromStr for Term {
+    type Err = ParserError;
+
+    /// Parse a str to a term; see `to_term`.
+    fn from_str(s: &str) -> ParserResult<Self> {
+        to_term(s)
+    }
+}
+
+impl FromStr for File {
+    type Err = ParserError;
+
+    /// Parse a str to a file; see `to_file`.
+    fn from_str(s: &str) -> ParserResult<Self> {
+        to_file(s)
+    }
+}
+
+impl FromStr for Library {
+    type Err = ParserError;
+
+    /// Parse a str to a library; see `to_library`.
+    fn from_str(s: &str) -> ParserResult<Self> {
+        to_library(s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,10 +632,7 @@ mod tests {
             #[test]
             fn $name() -> ParserResult<()> {
                 let term = to_term($input)?;
-                assert_eq!(
-                    term,
-                    $expected
-                );
+                assert_structural_eq!(term, $expected);
                 Ok(())
             }
             )*
@@ -160,10 +645,8 @@ mod tests {
             fn $name() -> ParserResult<()> {
                 let input = stringify!($name).to_string() + " := " + $input + ";";
                 let defn = M3LCParser::defn(M3LCParser::parse(Rule::defn, &input)?.single()?)?;
-                assert_eq!(
-                    defn,
-                    Defn::new(stringify!($name).into(), $expected)
-                );
+                assert_eq!(defn.name(), stringify!($name));
+                assert_structural_eq!(defn.term(), &$expected);
                 Ok(())
             }
             )*
@@ -172,6 +655,17 @@ mod tests {
 
     parser_tests! {
         identity: "fn x => x", Lam{ param: "x".into(), rule: "x".into() }
+        // the printer always parenthesizes these, but they don't need it at the very end
+        unparenthesized_lam_arg: "f fn x => x y", Appl{
+            left: "f".into(),
+            right: Lam{
+                param: "x".into(),
+                rule: Appl{
+                    left: "x".into(),
+                    right: "y".into()
+                }.into()
+            }.into()
+        }
         one: "fn f => fn a => f a", Lam{
             param: "f".into(),
             rule: Lam{
@@ -246,6 +740,7 @@ mod tests {
                 right: "z".into()
             }.into()
         }
+        wildcard: "fn _ => x", Lam{ param: "_".into(), rule: "x".into() }
         left_associative: "(x y) z", Appl{
             left: Appl{
                 left: "x".into(),
@@ -253,6 +748,367 @@ mod tests {
             }.into(),
             right: "z".into()
         }
+        hex: "0x10", Term::from(16)
+        bin: "0b1010", Term::from(10)
+        numeral_arg: "succ 0xF", Appl{
+            left: "succ".into(),
+            right: Term::from(15).into()
+        }
+    }
+
+    #[test]
+    fn term_from_str() -> ParserResult<()> {
+        let term: Term = "fn x => x".parse()?;
+        assert_eq!(term, to_term("fn x => x")?);
+        assert!("fn x =>".parse::<Term>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn file_from_str() -> ParserResult<()> {
+        let input = "ident := fn x => x;\nmain := ident;";
+        let file: File = input.parse()?;
+        assert_structural_eq!(file, to_file(input)?);
+        Ok(())
+    }
+
+    #[test]
+    /// Only prefixed literals are numerals; anything else made of digits is still a name.
+    fn numeric_literals() -> ParserResult<()> {
+        assert_eq!(to_term("0xff")?, to_term("0xFF")?);
+        assert_structural_eq!(to_term("0")?, "0".into());
+        assert_structural_eq!(to_term("0xfg")?, "0xfg".into());
+        assert_structural_eq!(to_term("0b12")?, "0b12".into());
+        assert!(to_file("0x10 := x; main := y").is_err());
+        assert!(to_term("fn 0b1 => x").is_err());
+        assert!(to_term("0x10000000000000000").is_err());
+        Ok(())
+    }
+
+    #[test]
+    /// Literals that would make huge numerals are rejected before building anything.
+    fn large_literals() -> ParserResult<()> {
+        assert_eq!(to_term("0x400")?, Term::from(1024));
+        assert!(to_term("0x401").is_err());
+        assert!(to_file("main := 0x100000;").is_err());
+        assert!(to_term("0xFFFFFFFFFFFF").is_err());
+        Ok(())
+    }
+
+    #[test]
+    /// `_` binds nothing, so it can't be used as a var or defn name.
+    fn wildcard_is_not_an_ident() {
+        assert!(to_term("_").is_err());
+        assert!(to_term("fn _ => _").is_err());
+        assert!(to_file("_ := x; main := y").is_err());
+        assert!(to_term("fn _x => _x").is_ok());
+    }
+
+    #[test]
+    fn idents() -> ParserResult<()> {
+        assert_structural_eq!(to_term("fn x' => x'")?, Term::lambda(&["x'"], "x'".into()));
+        assert_structural_eq!(to_term("fn x1 => x1")?, Term::lambda(&["x1"], "x1".into()));
+        assert_eq!(to_term("f'' x_2'")?, to_term("(f'') (x_2')")?);
+        assert_eq!(to_term("fn' in'")?, to_term("(fn') (in')")?);
+        assert!(to_term("fn x => x.1").is_err());
+        assert!(to_term("'x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn arity_annotation() -> ParserResult<()> {
+        let file = to_file("double : 1 := fn n => add n n; id := fn x => x; main := id")?;
+        assert_eq!(file.defns()[0].arity(), Some(1));
+        assert_eq!(file.defns()[1].arity(), None);
+        assert_structural_eq!(to_file(&file.to_string())?, file);
+        assert!(to_file("f : x := y; main := f").is_err());
+        assert!(to_file("f : 99999999999999999999999 := y; main := f").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn letrec() -> ParserResult<()> {
+        let input = "if := fn p => fn t => fn e => p t e;
+            is_zero := fn n => n (fn x => fn t => fn e => e) (fn t => fn e => t);
+            pred := fn n => fn f => fn x => n (fn g => fn h => h (g f)) (fn u => x) (fn u => u);
+            mul := fn m => fn n => fn f => m (n f);
+            1 := fn f => fn x => f x;
+            3 := fn f => fn x => f (f (f x));
+            main := letrec f := fn n => if (is_zero n) 1 (mul n (f (pred n))) in f 3";
+        let output = to_file(input)?.unroll().reduce(false);
+        assert_eq!(usize::try_from(&output).ok(), Some(6));
+        Ok(())
+    }
+
+    mod display_round_trip {
+        use super::*;
+        use crate::arbitrary::TermParams;
+        use proptest::prelude::*;
+
+        macro_rules! display_round_trip_tests { ($($name:ident: $ast:expr)*) => {
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let term: Term = $ast;
+                assert_structural_eq!(to_term(&term.to_string())?, term);
+                Ok(())
+            }
+            )*
+        }}
+
+        /// `fn x => x`
+        fn id() -> Term {
+            Term::lambda(&["x"], "x".into())
+        }
+
+        fn appl(left: Term, right: Term) -> Term {
+            Term::apply_all(left, vec![right])
+        }
+
+        display_round_trip_tests! {
+            lam_on_left: appl(id(), "y".into())
+            lam_on_right: appl("y".into(), id())
+            lam_on_both_sides: appl(id(), id())
+            lam_on_right_then_applied: appl(appl("f".into(), id()), "z".into())
+            lam_in_left_heavy_appl: appl(appl(id(), "y".into()), "z".into())
+            appl_on_right: appl("x".into(), appl("y".into(), "z".into()))
+            appl_of_lam_on_right: appl("x".into(), appl(id(), "y".into()))
+            lam_on_right_of_right: appl("x".into(), appl("y".into(), id()))
+            appl_in_body: Term::lambda(&["x"], appl(appl("x".into(), id()), "x".into()))
+            wildcard: appl(Term::lambda(&["_"], "y".into()), "z".into())
+        }
+
+        proptest! {
+            #[test]
+            fn any_term(term in any_with::<Term>(TermParams { depth: 5, size: 48, closed: false })) {
+                let parsed = to_term(&term.to_string());
+                prop_assert!(parsed.is_ok_and(|parsed| parsed.structural_eq(&term)));
+            }
+        }
+    }
+
+    #[test]
+    fn letrec_desugars_to_y() -> ParserResult<()> {
+        let term = to_term("letrec f := fn x => f x in f y")?;
+        let body = Term::fix(to_term("fn f => fn x => f x")?);
+        assert_eq!(term, Term::apply_all(to_term("fn f => f y")?, vec![body]));
+        Ok(())
+    }
+
+    mod reduce_source {
+        use super::*;
+
+        #[test]
+        fn normal_form() {
+            let output = reduce_source("two := fn f => fn a => f (f a); main := two two", 100);
+            assert_eq!(output.as_deref(), Ok("fn a => fn a1 => a (a (a (a a1)))"));
+        }
+
+        #[test]
+        fn invalid() {
+            assert!(reduce_source("main := fn x =>", 100).is_err());
+        }
+
+        #[test]
+        fn include() {
+            let err = reduce_source("include \"lib.m3lc\"; main := x", 100).unwrap_err();
+            assert!(err.contains("includes aren't supported"));
+        }
+
+        #[test]
+        fn diverges() {
+            let output = reduce_source("main := (fn x => x x) (fn x => x x)", 100);
+            assert_eq!(output, Err("no normal form after 100 steps".to_string()));
+        }
+    }
+
+    mod lint_source {
+        use super::*;
+
+        #[test]
+        fn positions() -> ParserResult<()> {
+            let input = "a := x;\n  b := a;\n  a := y;\nmain := b";
+            assert_eq!(
+                lint_source(input)?,
+                ["`a` is defined again at 3:3, shadowing the defn at 1:1"]
+            );
+            Ok(())
+        }
+
+        #[test]
+        /// Includes aren't resolved, so overriding an included defn isn't reported.
+        fn ignores_includes() -> ParserResult<()> {
+            let input = "include \"lib.m3lc\";\nid := fn x => x;\nmain := id";
+            assert!(lint_source(input)?.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn invalid() {
+            assert!(lint_source("a := ;").is_err());
+        }
+    }
+
+    #[test]
+    fn library() -> ParserResult<()> {
+        let input = "ident := fn x => x;\nzero := fn f => fn a => a;";
+        let lib = to_library(input)?;
+        let file = to_file(&(input.to_string() + "main := x"))?;
+        assert_structural_eq!(lib, Library::new(file.defns().to_vec()));
+        assert_structural_eq!(to_library(&lib.to_string())?, lib);
+        Ok(())
+    }
+
+    #[test]
+    fn library_rejects_main() {
+        assert!(to_library("ident := fn x => x; main := ident").is_err());
+    }
+
+    mod stream_reduce {
+        use super::*;
+
+        /// Collect the stream, stopping at the first error.
+        fn stream(source: &str) -> ParserResult<Vec<(String, Term)>> {
+            File::stream_reduce(source.as_bytes(), 1000).collect()
+        }
+
+        #[test]
+        fn agrees_with_reduce() -> ParserResult<()> {
+            let source = include_str!("../examples/church.m3lc");
+            let values = stream(source)?;
+            let names: Vec<_> = values.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, ["0", "succ", "1", "2", "3", "add", "times", "main"]);
+            assert_eq!(values[7].1, to_file(source)?.reduce(1000));
+            Ok(())
+        }
+
+        #[test]
+        /// Statements can span lines, and semicolons in comments don't end them.
+        fn multiline() -> ParserResult<()> {
+            let source = "id := # the identity; it's useful\n  fn x =>\n  x;;\nmain := id y";
+            assert_eq!(
+                stream(source)?,
+                [
+                    ("id".to_string(), to_term("fn x => x")?),
+                    ("main".to_string(), to_term("y")?),
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        /// Main isn't needed, or its name.
+        fn library() -> ParserResult<()> {
+            assert_eq!(stream("a := x;")?, [("a".to_string(), to_term("x")?)]);
+            assert_eq!(stream("a := x; a")?[1], ("main".to_string(), to_term("x")?));
+            Ok(())
+        }
+
+        #[test]
+        /// A forward reference stays free, even once the name is defined.
+        fn forward_reference() -> ParserResult<()> {
+            let values = stream("a := b; b := fn x => x; c := a; main := c;")?;
+            assert_eq!(values[2], ("c".to_string(), to_term("b")?));
+            assert_eq!(values[3], ("main".to_string(), to_term("b")?));
+            Ok(())
+        }
+
+        #[test]
+        fn main_not_last() {
+            let mut values = File::stream_reduce("main := x; a := y;".as_bytes(), 1000);
+            assert!(values.next().is_some_and(|value| value.is_ok()));
+            assert!(values.next().is_some_and(|value| value.is_err()));
+            assert!(values.next().is_none());
+        }
+
+        #[test]
+        fn stops_at_error() {
+            let mut values = File::stream_reduce("a := fn x =>; b := y;".as_bytes(), 1000);
+            assert!(values.next().is_some_and(|value| value.is_err()));
+            assert!(values.next().is_none());
+        }
+    }
+
+    mod include {
+        use super::*;
+
+        /// Make a fresh directory containing `files`, for testing includes.
+        fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+            let dir = std::env::temp_dir().join(format!("m3lc-include-{}", test));
+            let _ = fs::remove_dir_all(&dir);
+            for (name, contents) in files {
+                let path = dir.join(name);
+                fs::create_dir_all(path.parent().expect("files are in dir"))
+                    .expect("can create test dir");
+                fs::write(path, contents).expect("can write test file");
+            }
+            dir
+        }
+
+        #[test]
+        fn simple() -> ParserResult<()> {
+            let dir = write_files("simple", &[("lib.m3lc", "id := fn x => x;")]);
+            let file = to_file_in_dir("include \"lib.m3lc\";\nmain := id y", &dir)?;
+            assert_structural_eq!(file, to_file("id := fn x => x; main := id y")?);
+            Ok(())
+        }
+
+        #[test]
+        /// Includes in included files are relative to that file, and come first.
+        fn nested() -> ParserResult<()> {
+            let dir = write_files(
+                "nested",
+                &[
+                    ("std/bool.m3lc", "true := fn t => fn e => t;"),
+                    (
+                        "std/prelude.m3lc",
+                        "include \"bool.m3lc\"; false := fn t => fn e => e;",
+                    ),
+                ],
+            );
+            let file = to_file_in_dir("include \"std/prelude.m3lc\"; main := true", &dir)?;
+            let expected =
+                to_file("true := fn t => fn e => t; false := fn t => fn e => e; main := true")?;
+            assert_structural_eq!(file, expected);
+            Ok(())
+        }
+
+        #[test]
+        fn circular() {
+            let dir = write_files(
+                "circular",
+                &[
+                    ("a.m3lc", "include \"b.m3lc\"; a := x;"),
+                    ("b.m3lc", "include \"a.m3lc\"; b := y;"),
+                ],
+            );
+            let err = to_file_in_dir("include \"a.m3lc\"; main := a", &dir)
+                .expect_err("includes are circular");
+            assert!(err.to_string().contains("circular include"));
+        }
+
+        #[test]
+        fn missing() {
+            let dir = write_files("missing", &[]);
+            let err = to_file_in_dir("include \"nope.m3lc\"; main := a", &dir)
+                .expect_err("file doesn't exist");
+            assert!(err.to_string().contains("can't include"));
+        }
+
+        #[test]
+        /// Included files are libraries, so they can't have a main.
+        fn included_main() {
+            let dir = write_files("included_main", &[("lib.m3lc", "main := x")]);
+            assert!(to_file_in_dir("include \"lib.m3lc\"; main := a", &dir).is_err());
+        }
+
+        #[test]
+        /// `include` is only special at the top of the file.
+        fn include_as_ident() -> ParserResult<()> {
+            let file = to_file("include := fn x => x; main := include y")?;
+            assert_eq!(file.defns()[0].name(), "include");
+            Ok(())
+        }
     }
 
     #[test]
@@ -293,7 +1149,25 @@ mod tests {
             right: "zero".into(),
         };
         let expected = File::new(defns, main);
-        assert_eq!(to_file(input)?, expected);
+        assert_structural_eq!(to_file(input)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    /// Blank lines, odd spacing, and stray semicolons shouldn't change the parsed file.
+    fn file_messy_whitespace() -> ParserResult<()> {
+        let tidy = "ident := fn x => x;\nmain := ident ident;";
+        let messy = "\n\n  ident:=fn x=>x;;\n\n\n\tmain  :=\n  ident ident;\n\n  \n";
+        assert_structural_eq!(to_file(messy)?, to_file(tidy)?);
+        Ok(())
+    }
+
+    #[test]
+    fn file_optional_main_semicolon() -> ParserResult<()> {
+        let expected = to_file("ident := fn x => x;\nmain := ident;")?;
+        assert_structural_eq!(to_file("ident := fn x => x;\nmain := ident")?, expected);
+        assert_structural_eq!(to_file("ident := fn x => x;\nident;")?, expected);
+        assert_structural_eq!(to_file("ident := fn x => x;\nident")?, expected);
         Ok(())
     }
 
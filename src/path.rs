@@ -0,0 +1,114 @@
+//! Paths to subterms.
+use crate::grammar::Term;
+
+/// One step from a term to one of its immediate subterms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// From `fn x => t` to `t`.
+    LamBody,
+    /// From `t1 t2` to `t1`.
+    ApplLeft,
+    /// From `t1 t2` to `t2`.
+    ApplRight,
+}
+
+impl Term {
+    /// Get the immediate subterm in direction `dir`, if there is one.
+    fn child(&self, dir: Direction) -> Option<&Self> {
+        match (self, dir) {
+            (Self::Lam { rule, .. }, Direction::LamBody) => Some(rule),
+            (Self::Appl { left, .. }, Direction::ApplLeft) => Some(left),
+            (Self::Appl { right, .. }, Direction::ApplRight) => Some(right),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, dir: Direction) -> Option<&mut Self> {
+        match (self, dir) {
+            (Self::Lam { rule, .. }, Direction::LamBody) => Some(rule),
+            (Self::Appl { left, .. }, Direction::ApplLeft) => Some(left),
+            (Self::Appl { right, .. }, Direction::ApplRight) => Some(right),
+            _ => None,
+        }
+    }
+
+    /// Follow `path` from the root to a subterm.
+    ///
+    /// Returns `None` if the path doesn't fit the term, e.g. if it goes into the body of an
+    /// `Appl`. The empty path gives the term itself.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Direction};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("fn x => f x")?;
+    /// let path = [Direction::LamBody, Direction::ApplLeft];
+    /// assert_eq!(term.subterm_at(&path), Some(&"f".into()));
+    /// assert_eq!(term.subterm_at(&[Direction::ApplLeft]), None);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn subterm_at(&self, path: &[Direction]) -> Option<&Self> {
+        path.iter().try_fold(self, |term, &dir| term.child(dir))
+    }
+
+    /// Follow `path` from the root to a subterm, mutably.
+    ///
+    /// See `subterm_at`.
+    pub fn subterm_at_mut(&mut self, path: &[Direction]) -> Option<&mut Self> {
+        path.iter().try_fold(self, |term, &dir| term.child_mut(dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+    use Direction::{ApplLeft, ApplRight, LamBody};
+
+    #[test]
+    fn empty_path() -> ParserResult<()> {
+        let term = to_term("x y")?;
+        assert_eq!(term.subterm_at(&[]), Some(&term));
+        Ok(())
+    }
+
+    #[test]
+    fn deep() -> ParserResult<()> {
+        let term = to_term("(fn f => fn a => f (f a)) g")?;
+        let path = [ApplLeft, LamBody, LamBody, ApplRight, ApplRight];
+        assert_eq!(term.subterm_at(&path), Some(&"a".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() -> ParserResult<()> {
+        let term = to_term("fn x => x")?;
+        assert_eq!(term.subterm_at(&[ApplLeft]), None);
+        assert_eq!(term.subterm_at(&[LamBody, LamBody]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn mutate() -> ParserResult<()> {
+        let mut term = to_term("fn x => f x")?;
+        *term
+            .subterm_at_mut(&[LamBody, ApplLeft])
+            .expect("path is valid") = "g".into();
+        assert_eq!(term, to_term("fn x => g x")?);
+        Ok(())
+    }
+
+    #[test]
+    /// The paths `diff` reports point at the subterms it reports.
+    fn agrees_with_diff() -> ParserResult<()> {
+        let left = to_term("fn x => a (b x) c")?;
+        let right = to_term("fn y => a (b d) c")?;
+        let diff = left.diff(&right).expect("terms differ");
+        assert_eq!(left.subterm_at(&diff.path), Some(diff.left));
+        assert_eq!(right.subterm_at(&diff.path), Some(diff.right));
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+//! The parts of the std prelude that we use, for building without `std`.
+pub(crate) use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
@@ -1,91 +1,1021 @@
 //! Normal-order beta reduction of lambda terms.
-use std::{cell::RefCell, mem};
+use alloc::collections::BTreeSet;
+use core::{
+    fmt::Display,
+    mem,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+#[cfg(feature = "std")]
+use std::{
+    io::{self, Write},
+    ptr,
+    time::{Duration, Instant},
+};
 
-use crate::grammar::Term;
+#[cfg(feature = "std")]
+use colored::Colorize;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{grammar::Term, path::Direction, vars::CleanNames};
+
+/// How many steps `Term::reduce_timeout` takes between looking at the clock.
+///
+/// Reading the clock is slow next to a small step, so this keeps the overhead down.
+#[cfg(feature = "std")]
+const TIMEOUT_CHECK_INTERVAL: usize = 64;
+
+/// How many steps `Term::reduce_cancellable` takes between looking at the flag.
+const CANCEL_CHECK_INTERVAL: usize = 64;
+
+/// How a reduction ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReductionOutcome {
+    /// A normal form whose head is bound, i.e. an actual value.
+    NormalForm(Term),
+    /// A normal form whose head is a free variable, along with that variable.
+    ///
+    /// Usually this means the program referred to a name that was never defined.
+    Stuck(Term, String),
+}
+
+/// Statistics about a reduction, from `Term::reduce_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReduceStats {
+    /// How many steps were taken.
+    pub steps: usize,
+    /// The size of the biggest term along the way, including the first and last.
+    pub peak_size: usize,
+    /// Whether a normal form was reached within the step bound.
+    pub terminated: bool,
+}
+
+impl Display for ReduceStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} steps, peak size {}", self.steps, self.peak_size)?;
+        if !self.terminated {
+            write!(f, ", no normal form yet")?;
+        }
+        Ok(())
+    }
+}
+
+/// Lazily reduce each term in `terms`.
+///
+/// Each term is only reduced when the iterator reaches it, so results for earlier terms are
+/// available even if a later term diverges.
+///
+/// # Example
+/// ```
+/// # use m3lc::{reduce_all, to_term};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #
+/// let terms = vec![to_term("(fn x => x) y")?, to_term("(fn x => x x) (fn x => x x)")?];
+/// // only the first term is reduced, so this terminates
+/// assert_eq!(reduce_all(terms, false).next(), Some("y".into()));
+/// #
+/// # Ok(())}
+/// ```
+///
+/// # Panics
+/// Panics if `verbose` and stdout can't be written to; see `Term::reduce`.
+pub fn reduce_all<I: IntoIterator<Item = Term>>(
+    terms: I,
+    verbose: bool,
+) -> impl Iterator<Item = Term> {
+    terms.into_iter().map(move |term| term.reduce(verbose))
+}
 
 impl Term {
     /// Perform normal-order beta reduction.
     ///
+    /// If `verbose`, print each step to stdout; see `reduce_to_writer`. Without the `std`
+    /// feature, there's no stdout, so `verbose` does nothing. To route the steps somewhere else,
+    /// turn on the `log` feature, which emits a `trace` event for each step, along with the path
+    /// to the redex it contracts, whether or not `verbose`.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    ///
     /// # Safety
     /// The halting problem is a thing. Ergo, this can cause unhandled infinite regress.
     #[must_use]
     pub fn reduce(mut self, verbose: bool) -> Self {
-        while !self.is_irreducible() {
-            if verbose {
-                println!("{}", self);
+        self.reduce_in_place(verbose);
+        self
+    }
+
+    /// Perform normal-order beta reduction, then tidy up the normal form's bound variable names.
+    ///
+    /// Reduction leaves fresh names like `x.12` behind; this renames them once at the end, as
+    /// `names` says, instead of slowing down every step. If `verbose`, the steps are printed as
+    /// they are, like `reduce`.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    ///
+    /// # Safety
+    /// Like `reduce`, this can loop forever.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, CleanNames};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => fn y => x y) y")?;
+    /// let normal = term.reduce_clean(false, CleanNames::StripSuffixes);
+    /// assert_eq!(normal.to_string(), "fn y1 => y y1");
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn reduce_clean(self, verbose: bool, names: CleanNames) -> Self {
+        self.reduce(verbose).clean_names(names)
+    }
+
+    /// Perform normal-order beta reduction, replacing `self` with its normal form.
+    ///
+    /// This is `reduce` for when you don't own the term, e.g. it's in a struct field.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    ///
+    /// # Safety
+    /// Like `reduce`, this can loop forever.
+    pub fn reduce_in_place(&mut self, verbose: bool) {
+        #[cfg(feature = "std")]
+        if verbose {
+            self.reduce_in_place_to_writer(&mut io::stdout().lock())
+                .expect("failed printing to stdout");
+            return;
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = verbose;
+
+        while self.reduction_step() {}
+    }
+
+    /// Perform normal-order beta reduction, giving up if the term grows past `max_size` nodes.
+    ///
+    /// Some terms get huge on the way to a small normal form, so this guards against running out
+    /// of memory, where `reduce_bounded` guards against running out of time. A term that's
+    /// already in normal form is returned as-is, however big it is. If `verbose`, print each
+    /// step to stdout, like `reduce`.
+    ///
+    /// # Errors
+    /// Returns the partially-reduced term, which is bigger than `max_size`, if it grew too big.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => x x x) (fn x => x x x)")?;
+    /// assert!(term.reduce_size_capped(100, false).is_err());
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn reduce_size_capped(mut self, max_size: usize, verbose: bool) -> Result<Self, Self> {
+        loop {
+            if self.size() > max_size {
+                // it might have grown past the cap on the very step that made it normal
+                return if self.is_irreducible() {
+                    Ok(self)
+                } else {
+                    Err(self)
+                };
+            }
+            if !self.verbose_step(verbose) {
+                return Ok(self);
+            }
+        }
+    }
+
+    /// Perform normal-order beta reduction, giving up once `deadline` has passed.
+    ///
+    /// The clock is only checked every `TIMEOUT_CHECK_INTERVAL` steps, so this can run a little
+    /// past the deadline. If `verbose`, print each step to stdout, like `reduce`.
+    ///
+    /// # Errors
+    /// Returns the partially-reduced term if it didn't reach normal form in time.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// use std::time::Duration;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let omega = to_term("(fn x => x x) (fn x => x x)")?;
+    /// assert!(omega.reduce_timeout(Duration::from_millis(10), false).is_err());
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn reduce_timeout(self, deadline: Duration, verbose: bool) -> Result<Self, Self> {
+        let start = Instant::now();
+        self.reduce_until(TIMEOUT_CHECK_INTERVAL, verbose, || {
+            start.elapsed() >= deadline
+        })
+    }
+
+    /// Perform normal-order beta reduction, giving up once `cancel` is set.
+    ///
+    /// This lets another thread, e.g. a UI with a stop button, interrupt a reduction without
+    /// killing the thread doing it. The flag is checked before the first step and then every
+    /// `CANCEL_CHECK_INTERVAL` steps. If `verbose`, print each step to stdout, like `reduce`.
+    ///
+    /// # Errors
+    /// Returns the partially-reduced term if it was cancelled before reaching normal form.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// use std::sync::atomic::AtomicBool;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let omega = to_term("(fn x => x x) (fn x => x x)")?;
+    /// assert!(omega.reduce_cancellable(&AtomicBool::new(true), false).is_err());
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn reduce_cancellable(self, cancel: &AtomicBool, verbose: bool) -> Result<Self, Self> {
+        self.reduce_until(CANCEL_CHECK_INTERVAL, verbose, || {
+            cancel.load(Ordering::Relaxed)
+        })
+    }
+
+    /// Reduce like `reduce`, in batches of `interval` steps, until `should_stop` says to give up.
+    ///
+    /// `should_stop` is asked before every batch, including the first, so a reduction that's
+    /// already been stopped doesn't take any steps.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    fn reduce_until(
+        mut self,
+        interval: usize,
+        verbose: bool,
+        mut should_stop: impl FnMut() -> bool,
+    ) -> Result<Self, Self> {
+        loop {
+            if should_stop() {
+                return if self.is_irreducible() {
+                    Ok(self)
+                } else {
+                    Err(self)
+                };
+            }
+            for _ in 0..interval {
+                if !self.verbose_step(verbose) {
+                    return Ok(self);
+                }
+            }
+        }
+    }
+
+    /// Perform normal-order beta reduction, writing each step to `w`.
+    ///
+    /// Each term is written on its own line before it's reduced, so the normal form itself is not
+    /// written.
+    ///
+    /// # Errors
+    /// Returns any error from writing to `w`.
+    ///
+    /// # Safety
+    /// Like `reduce`, this can loop forever.
+    #[cfg(feature = "std")]
+    pub fn reduce_to_writer<W: Write>(mut self, w: &mut W) -> io::Result<Self> {
+        self.reduce_in_place_to_writer(w)?;
+        Ok(self)
+    }
+
+    #[cfg(feature = "std")]
+    fn reduce_in_place_to_writer<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        while self.reduction_step_to_writer(w)? {}
+        Ok(())
+    }
+
+    /// Perform normal-order beta reduction, giving up after `max_steps` steps.
+    ///
+    /// # Errors
+    /// Returns the partially-reduced term if it didn't reach normal form in time.
+    pub fn reduce_bounded(self, max_steps: usize) -> Result<Self, Self> {
+        self.reduce_bounded_by(max_steps, Self::reduction_step)
+    }
+
+    /// Perform applicative-order beta reduction, giving up after `max_steps` steps.
+    ///
+    /// Applicative order reduces the leftmost-innermost redex first, i.e. it normalizes arguments
+    /// before substituting them. It reaches the same normal form as `reduce` whenever it
+    /// terminates, but it diverges on some terms that normal order can handle, like
+    /// `(fn x => y) ((fn x => x x) (fn x => x x))`.
+    ///
+    /// # Errors
+    /// Returns the partially-reduced term if it didn't reach normal form in time.
+    pub fn reduce_applicative_bounded(self, max_steps: usize) -> Result<Self, Self> {
+        self.reduce_bounded_by(max_steps, Self::applicative_step)
+    }
+
+    /// Perform beta reduction in the order picked by `choose`.
+    ///
+    /// Before each step, `choose` gets the current term and returns the path to the redex to
+    /// contract next, or `None` to stop. This is for experimenting with reduction strategies; the
+    /// built-in ones are instances of it, e.g. normal order always picks the first of
+    /// `redex_paths`.
+    ///
+    /// # Panics
+    /// Panics if `choose` returns a path that doesn't lead to a redex.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// // rightmost-innermost
+    /// let term = to_term("(fn x => fn y => y) ((fn x => x) a) ((fn x => x) b)")?;
+    /// let normal = term.reduce_by(|term| term.redex_paths().pop());
+    /// assert_eq!(normal, "b".into());
+    /// #
+    /// # Ok(())}
+    /// ```
+    ///
+    /// # Safety
+    /// Like `reduce`, this can loop forever, depending on `choose`.
+    #[must_use]
+    pub fn reduce_by<F: FnMut(&Self) -> Option<Vec<Direction>>>(mut self, mut choose: F) -> Self {
+        while let Some(path) = choose(&self) {
+            assert!(
+                self.contract_at(&path),
+                "no redex at {:?} in `{}`",
+                path,
+                self
+            );
+        }
+        self
+    }
+
+    /// Reduce only the redexes at most `max_depth` steps below the root.
+    ///
+    /// The depth of a redex is the length of its path, so the root is at depth zero. Redexes are
+    /// contracted outermost and then leftmost first, like `reduce`, until every redex left is
+    /// deeper than `max_depth`. Contracting a redex can bring deeper ones up, in which case they
+    /// get contracted too.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => x) (f ((fn y => y) z))")?;
+    /// assert_eq!(term.clone().reduce_to_depth(0), to_term("f ((fn y => y) z)")?);
+    /// assert_eq!(term.reduce_to_depth(1), to_term("f z")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    ///
+    /// # Safety
+    /// Like `reduce`, this can loop forever.
+    #[must_use]
+    pub fn reduce_to_depth(self, max_depth: usize) -> Self {
+        self.reduce_by(|term| {
+            term.redex_paths()
+                .into_iter()
+                .find(|path| path.len() <= max_depth)
+        })
+    }
+
+    /// Perform up to `fuel` steps of normal-order beta reduction in place.
+    ///
+    /// Returns the number of steps actually taken. If that's less than `fuel`, the term reached
+    /// normal form; if it's exactly `fuel`, the last step may or may not have reached it, so check
+    /// with `contains_redex`. This is for reducing incrementally, e.g. in an interactive stepper
+    /// that wants to yield control between batches of steps.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut term = to_term("(fn x => x) ((fn y => y) z)")?;
+    /// assert_eq!(term.reduce_with_fuel(1), 1);
+    /// assert!(term.contains_redex());
+    /// assert_eq!(term.reduce_with_fuel(1), 1);
+    /// assert!(!term.contains_redex());
+    /// assert_eq!(term.reduce_with_fuel(5), 0);
+    /// assert_eq!(term, "z".into());
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn reduce_with_fuel(&mut self, fuel: usize) -> usize {
+        let mut steps = 0;
+        while steps < fuel && self.reduction_step() {
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Take up to `max_steps` steps with `step`, which returns whether it found a redex.
+    fn reduce_bounded_by(
+        mut self,
+        max_steps: usize,
+        step: fn(&mut Self) -> bool,
+    ) -> Result<Self, Self> {
+        for _ in 0..max_steps {
+            if !step(&mut self) {
+                return Ok(self);
+            }
+        }
+        if self.is_irreducible() {
+            Ok(self)
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Perform normal-order beta reduction, giving up after `max_steps` steps, and report how it
+    /// went.
+    ///
+    /// This counts steps like `reduce_bounded` and tracks the size like `reduce_size_capped`, but
+    /// in a single pass. The normal form is `None` if it wasn't reached in time.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, ReduceStats};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => x x) (fn y => y)")?;
+    /// let (normal, stats) = term.reduce_stats(10);
+    /// assert_eq!(normal, Some(to_term("fn y => y")?));
+    /// assert_eq!(
+    ///     stats,
+    ///     ReduceStats {
+    ///         steps: 2,
+    ///         peak_size: 7,
+    ///         terminated: true
+    ///     }
+    /// );
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn reduce_stats(mut self, max_steps: usize) -> (Option<Self>, ReduceStats) {
+        let mut stats = ReduceStats {
+            steps: 0,
+            peak_size: self.size(),
+            terminated: false,
+        };
+        while stats.steps < max_steps {
+            if !self.reduction_step() {
+                stats.terminated = true;
+                return (Some(self), stats);
+            }
+            stats.steps += 1;
+            stats.peak_size = stats.peak_size.max(self.size());
+        }
+        if self.is_irreducible() {
+            stats.terminated = true;
+            (Some(self), stats)
+        } else {
+            (None, stats)
+        }
+    }
+
+    /// Perform normal-order beta reduction, and check whether the result is stuck.
+    ///
+    /// A term is stuck if, under its leading lambdas, its head is a free variable, e.g.
+    /// `x (fn y => y)` or `fn a => x a`. No amount of reduction can make progress past it.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, ReductionOutcome};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => undefined x) y")?;
+    /// assert_eq!(
+    ///     term.beta_nf_or_stuck(),
+    ///     ReductionOutcome::Stuck(to_term("undefined y")?, "undefined".to_string())
+    /// );
+    /// #
+    /// # Ok(())}
+    /// ```
+    ///
+    /// # Safety
+    /// Like `reduce`, this can loop forever.
+    #[must_use]
+    pub fn beta_nf_or_stuck(self) -> ReductionOutcome {
+        let normal_form = self.reduce(false);
+        match normal_form.stuck_head() {
+            Some(head) => ReductionOutcome::Stuck(normal_form, head),
+            None => ReductionOutcome::NormalForm(normal_form),
+        }
+    }
+
+    /// Find the free variable in head position, if there is one.
+    pub(crate) fn stuck_head(&self) -> Option<String> {
+        let mut body = self;
+        let mut bound = vec![];
+        while let Self::Lam { param, rule } = body {
+            bound.push(param.as_str());
+            body = rule;
+        }
+        match body.spine().0 {
+            Self::Var(x) if !bound.contains(&x.as_str()) => Some(x.clone()),
+            _ => None,
+        }
+    }
+
+    /// Check that `self` and `other` reduce to alpha-equivalent normal forms.
+    ///
+    /// By confluence this always holds when `other` is reachable from `self` by some sequence of
+    /// reductions (in any order), so this is mostly useful for testing.
+    ///
+    /// # Safety
+    /// Like `reduce`, this can loop forever.
+    #[must_use]
+    pub fn confluent_with(&self, other: &Self) -> bool {
+        self.clone()
+            .reduce(false)
+            .alpha_equiv(&other.clone().reduce(false))
+    }
+
+    /// Check whether `self` and `other` reduce to alpha-equivalent normal forms, spending at most
+    /// `max_steps` steps between them.
+    ///
+    /// This is `confluent_with` for terms that might diverge, e.g. when testing two
+    /// implementations of the same function against each other. `self` is reduced first, and
+    /// `other` gets whatever steps are left.
+    ///
+    /// Returns `None` if either term doesn't reach normal form within the budget, since then we
+    /// don't know whether they're equal.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let two = Term::from(2);
+    /// assert_eq!(two.clone().succ().equal_under_reduction(&3.into(), 100), Some(true));
+    /// assert_eq!(two.clone().succ().equal_under_reduction(&two, 100), Some(false));
+    ///
+    /// let omega = to_term("(fn x => x x) (fn x => x x)")?;
+    /// assert_eq!(omega.equal_under_reduction(&omega, 100), None);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn equal_under_reduction(&self, other: &Self, max_steps: usize) -> Option<bool> {
+        let mut left = self.clone();
+        let steps = left.reduce_with_fuel(max_steps);
+        if !left.is_irreducible() {
+            return None;
+        }
+        let right = other.clone().reduce_bounded(max_steps - steps).ok()?;
+        Some(left.alpha_equiv(&right))
+    }
+
+    /// Contract every redex in the term at once.
+    ///
+    /// This is a complete development: each redex that's in the term before the step is
+    /// contracted, inner ones first, but redexes created by the step are left for the next one.
+    /// A redex the term doesn't otherwise need (like an argument that's thrown away) still gets
+    /// contracted, but in exchange, a term with many independent redexes needs far fewer passes.
+    /// Be aware that a single pass can make the term much bigger, since it contracts the redexes
+    /// in an argument before copying it around.
+    ///
+    /// Returns whether there were any redexes to contract; if not, the term is unchanged.
+    pub fn reduce_parallel_step(&mut self) -> bool {
+        match self {
+            Self::Var(_) => false,
+            Self::Lam { rule, .. } => rule.reduce_parallel_step(),
+            Self::Appl { left, right } => {
+                let redex = matches!(left, box Self::Lam { .. });
+                // a lam on the left stays a lam, so this contracts inside its rule; both sides
+                // need stepping, so no short-circuiting
+                let left_stepped = left.reduce_parallel_step();
+                let right_stepped = right.reduce_parallel_step();
+                if redex {
+                    self.apply();
+                }
+                redex || left_stepped || right_stepped
             }
-            self.reduction_step();
         }
+    }
+
+    /// Perform parallel beta reduction, i.e. `reduce_parallel_step` until the term is irreducible.
+    ///
+    /// This is the Gross-Knuth strategy, which (like normal order) finds the normal form whenever
+    /// there is one, so this gives the same result as `reduce`, up to alpha-equivalence.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn t => fn e => t) x ((fn x => x x) (fn x => x x))")?;
+    /// assert_eq!(term.reduce_parallel(), to_term("x")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    ///
+    /// # Safety
+    /// The halting problem is a thing. Ergo, this can cause unhandled infinite regress.
+    #[must_use]
+    pub fn reduce_parallel(mut self) -> Self {
+        while self.reduce_parallel_step() {}
+        self
+    }
+
+    /// Contract only the redexes that are cheap to inline.
+    ///
+    /// A redex is contracted if its argument is a var, or if it's a lambda and the param occurs
+    /// free at most once, so a complex argument is never duplicated. Every such step shrinks the
+    /// term, so unlike `reduce` this always terminates. All other redexes are left alone.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => f x x) y ((fn z => z z) (g w))")?;
+    /// assert_eq!(term.simplify(), to_term("f y y ((fn z => z z) (g w))")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn simplify(mut self) -> Self {
+        self.simplify_in_place();
         self
     }
 
-    fn reduction_step(&mut self) {
+    fn simplify_in_place(&mut self) {
+        match self {
+            Self::Var(_) => (),
+            Self::Lam { rule, .. } => rule.simplify_in_place(),
+            Self::Appl { left, right } => {
+                left.simplify_in_place();
+                right.simplify_in_place();
+                let cheap = match (left.as_ref(), right.as_ref()) {
+                    (Self::Lam { .. }, Self::Var(_)) => true,
+                    (Self::Lam { param, rule }, Self::Lam { .. }) => {
+                        rule.count_variable_occurrences(param) <= 1
+                    }
+                    _ => false,
+                };
+                if cheap {
+                    // the substitution can create new cheap redexes
+                    self.apply();
+                    self.simplify_in_place();
+                }
+            }
+        }
+    }
+
+    /// Find the paths to every redex in the term, outermost and then leftmost first.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Direction};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn x => x) ((fn y => y) z)")?;
+    /// assert_eq!(term.redex_paths(), vec![vec![], vec![Direction::ApplRight]]);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn redex_paths(&self) -> Vec<Vec<Direction>> {
+        let mut out = vec![];
+        self.redex_paths_impl(&mut vec![], &mut out);
+        out
+    }
+
+    fn redex_paths_impl(&self, path: &mut Vec<Direction>, out: &mut Vec<Vec<Direction>>) {
+        match self {
+            Self::Var(_) => (),
+            Self::Lam { rule, .. } => {
+                path.push(Direction::LamBody);
+                rule.redex_paths_impl(path, out);
+                path.pop();
+            }
+            Self::Appl { left, right } => {
+                if let box Self::Lam { .. } = left {
+                    out.push(path.clone());
+                }
+                path.push(Direction::ApplLeft);
+                left.redex_paths_impl(path, out);
+                path.pop();
+                path.push(Direction::ApplRight);
+                right.redex_paths_impl(path, out);
+                path.pop();
+            }
+        }
+    }
+
+    /// Contract the redex at `path`.
+    ///
+    /// Returns whether there was a redex there to contract; if not, the term is unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Direction};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut term = to_term("(fn x => x) ((fn y => y) z)")?;
+    /// assert!(term.contract_at(&[Direction::ApplRight]));
+    /// assert_eq!(term, to_term("(fn x => x) z")?);
+    /// assert!(!term.contract_at(&[Direction::ApplRight]));
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn contract_at(&mut self, path: &[Direction]) -> bool {
+        match self.subterm_at_mut(path) {
+            Some(
+                redex @ Self::Appl {
+                    left: box Self::Lam { .. },
+                    ..
+                },
+            ) => {
+                redex.apply();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Format the term with the redex that normal order will contract next highlighted.
+    ///
+    /// The highlighted redex is exactly the one the next step of `reduce` contracts. If the term
+    /// is irreducible, this is the same as `to_string`.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn display_with_redex_highlight(&self) -> String {
+        let redex = self.next_redex();
+        self.fmt_with(&|term, s| {
+            if redex.is_some_and(|redex| ptr::eq(term, redex)) {
+                s.yellow().bold().to_string()
+            } else {
+                s
+            }
+        })
+    }
+
+    /// Find the redex that `reduction_step` will contract, if any.
+    #[cfg(feature = "std")]
+    fn next_redex(&self) -> Option<&Self> {
+        self.subterm_at(&self.next_redex_path()?)
+    }
+
+    /// Find the path to the redex that `reduction_step` will contract, if any.
+    ///
+    /// This has to follow exactly the same rules as `reduction_step`.
+    fn next_redex_path(&self) -> Option<Vec<Direction>> {
+        let mut path = vec![];
+        let mut curr = self;
+        loop {
+            match curr {
+                Self::Var(_) => return None,
+                Self::Lam { rule, .. } => {
+                    path.push(Direction::LamBody);
+                    curr = rule;
+                }
+                Self::Appl { left, right } => {
+                    if let box Self::Lam { .. } = left {
+                        return Some(path);
+                    } else if left.is_irreducible() {
+                        path.push(Direction::ApplRight);
+                        curr = right;
+                    } else {
+                        path.push(Direction::ApplLeft);
+                        curr = left;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reduce the term, recording where each step happened.
+    ///
+    /// Each entry is a term along the way to the normal form, paired with the path to the redex
+    /// that the next step contracts there. The normal form itself isn't included, since there's
+    /// no redex in it.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Direction};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("x ((fn y => y) z)")?;
+    /// let trace = term.clone().reduction_trace();
+    /// assert_eq!(trace, vec![(term, vec![Direction::ApplRight])]);
+    /// #
+    /// # Ok(())}
+    /// ```
+    ///
+    /// # Safety
+    /// The halting problem is a thing. Ergo, this can cause unhandled infinite regress.
+    #[must_use]
+    pub fn reduction_trace(mut self) -> Vec<(Self, Vec<Direction>)> {
+        let mut trace = vec![];
+        while let Some(path) = self.next_redex_path() {
+            trace.push((self.clone(), path));
+            let stepped = self.reduction_step();
+            debug_assert!(stepped, "there was a redex to contract");
+        }
+        trace
+    }
+
+    /// Perform a single step of normal-order beta reduction.
+    ///
+    /// Returns whether there was a redex to contract; if not, the term is already in normal form
+    /// and is unchanged. This is the building block for driving reduction by hand, e.g. to show
+    /// each step in an interactive tool.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut term = to_term("(fn x => x) ((fn y => y) z)")?;
+    /// let mut steps = 0;
+    /// while term.beta_reduce_one() {
+    ///     steps += 1;
+    /// }
+    /// assert_eq!(term, to_term("z")?);
+    /// assert_eq!(steps, 2);
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn beta_reduce_one(&mut self) -> bool {
+        self.reduction_step()
+    }
+
+    /// Contract the leftmost-outermost redex, returning whether there was one.
+    ///
+    /// Looking for the redex is how we find out whether the term is irreducible, so loops should
+    /// use the return value instead of calling `is_irreducible` first, which walks the term all
+    /// over again. With the `log` feature, this emits a `trace` event with the term and the path
+    /// to the redex first.
+    #[must_use]
+    pub(crate) fn reduction_step(&mut self) -> bool {
+        #[cfg(feature = "log")]
+        if log::log_enabled!(log::Level::Trace) {
+            if let Some(path) = self.next_redex_path() {
+                log::trace!("{} (redex at {:?})", self, path);
+            }
+        }
+        self.reduction_step_impl()
+    }
+
+    /// Take a step like `reduction_step`, printing the term to stdout first if `verbose`.
+    ///
+    /// See `reduction_step_to_writer`.
+    ///
+    /// # Panics
+    /// Panics if `verbose` and stdout can't be written to, just like `println!`.
+    fn verbose_step(&mut self, verbose: bool) -> bool {
+        #[cfg(feature = "std")]
+        if verbose {
+            return self
+                .reduction_step_to_writer(&mut io::stdout().lock())
+                .expect("failed printing to stdout");
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = verbose;
+
+        self.reduction_step()
+    }
+
+    /// Take a step like `reduction_step`, writing the term to `w` first if there was a redex.
+    ///
+    /// The term is formatted before the step, but only written once the step finds a redex, so
+    /// the normal form isn't written, without looking for the redex twice.
+    #[cfg(feature = "std")]
+    fn reduction_step_to_writer<W: Write>(&mut self, w: &mut W) -> io::Result<bool> {
+        let before = self.to_string();
+        let stepped = self.reduction_step();
+        if stepped {
+            writeln!(w, "{}", before)?;
+        }
+        Ok(stepped)
+    }
+
+    fn reduction_step_impl(&mut self) -> bool {
+        // This walks down the term with a loop rather than recursing, so deep terms like big
+        // numerals don't overflow the stack. `pending` holds the right sides of the appls we went
+        // left in, innermost last, to look in if there's no redex on the left.
+        let mut pending: Vec<&mut Self> = vec![];
+        let mut term = self;
+        loop {
+            if let Self::Appl {
+                left: box Self::Lam { .. },
+                ..
+            } = term
+            {
+                // -------------------------
+                // (fn x => t) s ~~> [s/x] t
+                //
+                // We have a special method here, `apply`, which does some performance hacks on
+                // top of `subst` to avoid unnecessary clones. That's documented in the body of
+                // that method.
+                term.apply();
+                return true;
+            }
+            term = match term {
+                Self::Var(_) => match pending.pop() {
+                    Some(right) => right,
+                    None => return false,
+                },
+
+                //           t ~~> t'
+                // ----------------------------
+                // (fn x => t) ~~> (fn x => t')
+                Self::Lam { rule, .. } => rule,
+
+                // Left is not a lambda, so if there's a redex on the left, it's inside it:
+                //
+                //      t1 ~~> t1'
+                // ----------------------
+                //  (t1 t2) ~~> (t1' t2)
+                //
+                // and otherwise the left is irreducible:
+                //
+                // t1 irr    t2 ~~> t2'
+                // ----------------------
+                //  (t1 t2) ~~> (t1 t2')
+                Self::Appl { left, right } => {
+                    pending.push(right);
+                    left
+                }
+            };
+        }
+    }
+
+    /// Contract the leftmost-innermost redex, returning whether there was one.
+    fn applicative_step(&mut self) -> bool {
         match self {
-            // If we get here, then there's a bug and reduce will loop infinitely, so better to
-            // fail fast.
-            Self::Var(_) => unreachable!("vars are irreducible"),
+            Self::Var(_) => false,
 
             //           t ~~> t'
             // ----------------------------
             // (fn x => t) ~~> (fn x => t')
-            Self::Lam { rule, .. } => rule.reduction_step(),
+            Self::Lam { rule, .. } => rule.applicative_step(),
 
             Self::Appl { left, right } => {
-                if let box Self::Lam { .. } = left {
-                    // -------------------------
-                    // (fn x => t) s ~~> [s/x] t
-                    //
-                    // We have a special method here, `apply`, which does some performance hacks on
-                    // top of `subst` to avoid unnecessary clones. That's documented in the body of
-                    // that method.
+                //    t1 ~~> t1'
+                // ----------------
+                // t1 t2 ~~> t1' t2
+                //
+                // t1 irr    t2 ~~> t2'
+                // --------------------
+                //   t1 t2 ~~> t1 t2'
+                if left.applicative_step() || right.applicative_step() {
+                    return true;
+                }
+                // Both sides are irreducible, so the whole is only reducible if it's a lam
+                // applied to something:
+                //
+                //       t irr      s irr
+                // -------------------------
+                // (fn x => t) s ~~> [s/x] t
+                let is_redex = matches!(left, box Self::Lam { .. });
+                if is_redex {
                     self.apply();
-                } else if left.is_irreducible() {
-                    // t1 irr    t2 ~~> t2'
-                    // ----------------------
-                    //  (t1 t2) ~~> (t1 t2')
-                    right.reduction_step();
-                } else {
-                    // Left is not a lambda, because that was checked earlier, and not a var,
-                    // because it's reducible. Therefore it's an appl, and one of these rules
-                    // applies:
-                    //
-                    //          t1 ~~> t1'
-                    // ------------------------------
-                    // ((t1 t2) t3) ~~> ((t1' t2) t3)
-                    //
-                    //     t1 irr      t2 ~~> t2'
-                    // ------------------------------
-                    // ((t1 t2) t3) ~~> ((t1 t2') t3)
-                    left.reduction_step();
                 }
+                is_redex
             }
         }
     }
 
     /// Given an appl with a lam on the left, apply the left to the right.
     fn apply(&mut self) {
-        // Put a placeholder into self so we get ownership of the dereferenced value. Note that
-        // empty strings don't allocate.
-        let self_owned = mem::replace(self, Self::Var(String::new()));
-
         // We have to traverse down the struct to get to the lambda on the left. This is guaranteed
         // to be ok, because `apply` can only be called when we've matched exactly this pattern
         // already.
         if let Self::Appl {
-            left: box Self::Lam {
-                param,
-                box mut rule,
-            },
-            box right,
-        } = self_owned
+            left: box Self::Lam { param, rule },
+            right,
+        } = self
         {
-            rule.subst(&param, &right);
+            // Put a placeholder into the lambda so we get ownership of its rule; `Term` has a
+            // `Drop` impl, so we can't just move out of `self`. Note that empty strings don't
+            // allocate.
+            let mut rule = mem::replace(&mut **rule, Self::Var(String::new()));
+            rule.subst(param, &**right);
 
-            // Now we can write `rule` into the memory of `self` (currently occupied by the
-            // placeholder `Var("")`). If we hadn't done the `mem::replace" trick, this would
-            // break borrow rules, because it would require a mutable reference to `self` and a
-            // reference to `right` (which `rule` depends on). So unless we wanted to use
-            // `unsafe`, we'd either have to clone `right` or clone `rule`.
+            // Now we can write `rule` into the memory of `self`, dropping the rest of the redex.
+            // If we hadn't done the `mem::replace` trick, this would break borrow rules, because
+            // it would require a mutable reference to `self` and a reference to `right` (which
+            // `rule` depends on). So unless we wanted to use `unsafe`, we'd either have to clone
+            // `right` or clone `rule`.
             *self = rule;
         } else {
             unreachable!("apply only called with appl with lam on left");
@@ -93,69 +1023,219 @@ impl Term {
     }
 
     /// Check whether the term is beta-reducible.
-    fn is_irreducible(&self) -> bool {
-        match self {
-            // -----
-            // x irr
-            Self::Var(_) => true,
+    pub(crate) fn is_irreducible(&self) -> bool {
+        // Like `reduction_step_impl`, this loops with a stack of right sides still to check,
+        // rather than recursing.
+        let mut pending = vec![];
+        let mut term = self;
+        loop {
+            term = match term {
+                // -----
+                // x irr
+                Self::Var(_) => match pending.pop() {
+                    Some(right) => right,
+                    None => return true,
+                },
 
-            Self::Appl { left, right } => {
-                if let box Self::Lam { .. } = left {
-                    // Lams applied to terms are always reducible.
-                    false
-                } else {
-                    // Follows from one of these rules, depending on the variant of left:
-                    //
-                    //  (t1 t2) irr    t3 irr
-                    // ----------------------
-                    //    ((t1 t2) t3) irr
-                    //
-                    //   t irr
-                    // ---------
-                    // (x t) irr
-                    left.is_irreducible() && right.is_irreducible()
+                // Lams applied to terms are always reducible.
+                Self::Appl {
+                    left: box Self::Lam { .. },
+                    ..
+                } => return false,
+
+                // Follows from one of these rules, depending on the variant of left:
+                //
+                //  (t1 t2) irr    t3 irr
+                // ----------------------
+                //    ((t1 t2) t3) irr
+                //
+                //   t irr
+                // ---------
+                // (x t) irr
+                Self::Appl { left, right } => {
+                    pending.push(right);
+                    left
                 }
+
+                //      t irr
+                // ---------------
+                // (fn x => t) irr
+                Self::Lam { rule, .. } => rule,
+            };
+        }
+    }
+
+    /// Count the beta-redexes in the term, i.e. subterms of the form `(fn x => t) s`.
+    ///
+    /// Redexes nested inside other redexes count separately.
+    #[must_use]
+    pub fn redex_count(&self) -> usize {
+        match self {
+            Self::Var(_) => 0,
+            Self::Lam { rule, .. } => rule.redex_count(),
+            Self::Appl { left, right } => {
+                let here = usize::from(matches!(left, box Self::Lam { .. }));
+                here + left.redex_count() + right.redex_count()
             }
+        }
+    }
 
-            //      t irr
-            // ---------------
-            // (fn x => t) irr
-            Self::Lam { rule, .. } => rule.is_irreducible(),
+    /// Check whether the term contains a beta-redex anywhere.
+    ///
+    /// Equivalent to `self.redex_count() > 0`, but stops at the first redex it finds.
+    #[must_use]
+    pub fn contains_redex(&self) -> bool {
+        match self {
+            Self::Var(_) => false,
+            Self::Lam { rule, .. } => rule.contains_redex(),
+            Self::Appl {
+                left: box Self::Lam { .. },
+                ..
+            } => true,
+            Self::Appl { left, right } => left.contains_redex() || right.contains_redex(),
         }
     }
 
     /// Perform substitution of `replace` for `with` in `self`.
-    fn subst<T>(&mut self, replace: &str, with: &T)
+    ///
+    /// In debug builds, this checks the result with `assert_no_capture`.
+    pub(crate) fn subst<T: Substitute>(&mut self, replace: &str, with: &T) {
+        #[cfg(debug_assertions)]
+        let before = self.clone();
+        self.subst_impl(replace, with);
+        #[cfg(debug_assertions)]
+        self.assert_no_capture(&before, replace, &with.clone().into());
+    }
+
+    fn subst_impl<T>(&mut self, replace: &str, with: &T)
     where
         // Into<Self> so we can pass &strs, so we don't have to clone new_var until needed.
         // Refs so we can wait to clone until we need to. (Aka, this is a polluted type signature
         // in exchange for a ~10x speedup because of the avoided clones. Previously, we had to
         // clone every time we recursed into an `Appl`.)
-        T: Into<Self> + Clone,
+        T: Substitute,
     {
-        match self {
-            // [s/x] x := s
-            // Only clone we have to do in this whole process is here.
-            Self::Var(s) if s == replace => *self = with.clone().into(),
+        // This loops rather than recursing down rules and right sides, so long chains of appls,
+        // like big numerals, don't overflow the stack.
+        let mut term = self;
+        loop {
+            term = match term {
+                // [s/x] x := s
+                // Only clone we have to do in this whole process is here.
+                Self::Var(s) if s == replace => {
+                    *term = with.clone().into();
+                    return;
+                }
 
-            // [s/x] y := y
-            Self::Var(_) => (),
+                // [s/x] y := y
+                Self::Var(_) => return,
+
+                // [s/x] (fn x => t) := (fn x => t)
+                Self::Lam { param, .. } if param == replace => return,
 
-            // [s/x] (fn x => t) := (fn x => t)
-            Self::Lam { param, .. } if param == replace => (),
+                Self::Lam { param, rule } => {
+                    if with.occurs_free(param) {
+                        // [s/x] (fn y => t) := (fn y => t) if x is not free in t
+                        // y could capture something in s, but there's nowhere in t for s to go,
+                        // so there's no need to rename.
+                        if !rule.occurs_free(replace) {
+                            return;
+                        }
+
+                        // [s/x] (fn y => t) := (fn z => [s/x] ([z/y] t)) for fresh z
+                        let new_var = get_fresh_ident(param);
+                        rule.subst_impl(param, &new_var);
+                        *param = new_var;
+                    }
+                    // [s/x] (fn y => t) := (fn y => [s/x] t) if y is not free in s
+                    // There's nothing for y to capture, so there's no need to rename it. This
+                    // saves a traversal of t and a call to `get_fresh_ident`, which is expensive.
+                    // In particular, `_` can't be referenced, so it's never renamed.
+                    rule
+                }
+
+                // [s/x] (t1 t2) := ([s/x] t1) ([s/x] t2)
+                Self::Appl { left, right } => {
+                    left.subst_impl(replace, with);
+                    right
+                }
+            };
+        }
+    }
+
+    /// Substitute every `(var, with)` pair in `subs` into the term at once.
+    ///
+    /// Unlike a sequence of `subst`s, the `with`s are never substituted into each other, so
+    /// `[y/x, z/y] (x y)` is `y z`, not `z z`. Params are renamed, once, if they'd capture a free
+    /// var of any `with` that gets substituted under them. If a var appears more than once in
+    /// `subs`, the first pair wins.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut term = to_term("fn a => x y")?;
+    /// term.substitute_many(&[("x".into(), "y".into()), ("y".into(), "a".into())]);
+    /// assert_eq!(term, to_term("fn b => y a")?);
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn substitute_many(&mut self, subs: &[(String, Self)]) {
+        let subs: Vec<_> = subs
+            .iter()
+            .map(|(var, with)| (var.as_str(), with))
+            .collect();
+        self.substitute_many_impl(&subs);
+    }
+
+    pub(crate) fn substitute_many_impl(&mut self, subs: &[(&str, &Self)]) {
+        // Capture checks need the free vars of each `with` at every lambda, so work them out once
+        // up front rather than walking the `with`s again each time.
+        let free: Vec<_> = subs.iter().map(|(_, with)| with.free_vars()).collect();
+        let subs: Vec<_> = subs
+            .iter()
+            .zip(&free)
+            .map(|(&(var, with), free)| (var, with, free))
+            .collect();
+        self.substitute_many_with_free(&subs);
+    }
+
+    /// `substitute_many_impl`, with the free vars of each `with` alongside it.
+    fn substitute_many_with_free(&mut self, subs: &[(&str, &Self, &BTreeSet<&str>)]) {
+        match self {
+            Self::Var(x) => {
+                if let Some((_, with, _)) = subs.iter().find(|(var, ..)| var == x) {
+                    *self = (*with).clone();
+                }
+            }
 
-            // [s/x] (fn y => t) := (fn z => [s/x] ([z/y] t)) for fresh z
             Self::Lam { param, rule } => {
-                let new_var = get_fresh_ident(param);
-                rule.subst(param, &new_var);
-                rule.subst(replace, with);
-                *param = new_var; // we need new_var for the param and the recursive subst
+                // a binder for a var shadows it, so that substitution stops here
+                let subs: Vec<_> = subs
+                    .iter()
+                    .filter(|(var, ..)| var != param)
+                    .copied()
+                    .collect();
+                if subs.is_empty() {
+                    return;
+                }
+                // Only walk the rule to see if a var is there when its `with` would be captured,
+                // which is rare.
+                if subs
+                    .iter()
+                    .any(|(var, _, free)| free.contains(param.as_str()) && rule.occurs_free(var))
+                {
+                    let new_var = get_fresh_ident(param);
+                    rule.subst(param, &new_var);
+                    *param = new_var;
+                }
+                rule.substitute_many_with_free(&subs);
             }
 
-            // [s/x] (t1 t2) := ([s/x] t1) ([s/x] t2)
             Self::Appl { left, right } => {
-                left.subst(replace, with);
-                right.subst(replace, with);
+                left.substitute_many_with_free(subs);
+                right.substitute_many_with_free(subs);
             }
         }
     }
@@ -163,69 +1243,150 @@ impl Term {
     /// Check term equivalence under alpha-renaming.
     #[must_use]
     pub fn alpha_equiv(&self, other: &Self) -> bool {
-        self.alpha_equiv_impl(other, &mut vec![])
+        self.alpha_equiv_impl(other, &mut vec![], None)
     }
 
-    fn alpha_equiv_impl<'a>(&'a self, other: &'a Self, ctx: &mut Vec<(&'a str, &'a str)>) -> bool {
+    /// Check term equivalence under alpha-renaming, returning the correspondence between binders.
+    ///
+    /// On success, this is the `(param, other_param)` pair for every pair of matching lambdas,
+    /// in the order they appear in the terms. Binders that already agree are included too, so
+    /// the witness has one pair per lambda.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let left = to_term("fn x => fn y => x y")?;
+    /// let right = to_term("fn a => fn y => a y")?;
+    /// assert_eq!(
+    ///     left.alpha_equiv_witness(&right),
+    ///     Some(vec![("x".into(), "a".into()), ("y".into(), "y".into())])
+    /// );
+    /// assert_eq!(left.alpha_equiv_witness(&to_term("fn a => fn b => b a")?), None);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn alpha_equiv_witness(&self, other: &Self) -> Option<Vec<(String, String)>> {
+        let mut witness = vec![];
+        self.alpha_equiv_impl(other, &mut vec![], Some(&mut witness))
+            .then(|| {
+                witness
+                    .into_iter()
+                    .map(|(a, b)| (a.to_string(), b.to_string()))
+                    .collect()
+            })
+    }
+
+    /// If `witness` is given, every binding pushed onto `ctx` is also recorded there.
+    fn alpha_equiv_impl<'a>(
+        &'a self,
+        other: &'a Self,
+        ctx: &mut Vec<(&'a str, &'a str)>,
+        mut witness: Option<&mut Vec<(&'a str, &'a str)>>,
+    ) -> bool {
         // The idea is to maintain a context which stores the existing lambda abstractions, _in
         // order_. This context essentially associates variables from each term. We can therefore use
         // this to check equivalence whenever we see a `Var`.
         //
         // We don't want to use `subst` here because a big motivation for implementing this function
         // is to enable testing `subst` without relying on implementation details of `get_fresh`.
-        match (self, other) {
-            // handling var: if x and y are most recently bound in the same lambda, return true
-            (Self::Var(x), Self::Var(y)) => {
-                #[allow(clippy::map_unwrap_or)] // slight performance tradeoff, but more readable
-                ctx.iter()
-                    .rfind(|(a, b)| a == x || b == y) // find the most recent binding of x or y
-                    .map(|(a, b)| a == x && b == y) // it should also bind the other
-                    .unwrap_or(x == y) // if neither is bound, they should be equal
-            }
-
-            // handling lam: store params in the ctx and recurse on the rules
-            (
-                Self::Lam {
-                    param: param1,
-                    rule: rule1,
-                },
-                Self::Lam {
-                    param: param2,
-                    rule: rule2,
-                },
-            ) => {
-                // Push the new binding onto the context, compare the rules, then pop it off the
-                // context so that parent calls don't inherit our binding.
-                ctx.push((param1, param2));
-                let out = rule1.alpha_equiv_impl(rule2, ctx);
-                ctx.pop();
-                out
-            }
+        //
+        // Rather than recursing, which overflows the stack on deep terms like big numerals, we
+        // keep a stack of pairs of subterms still to compare, along with how many bindings were in
+        // the context where they are; the context is cut back to that before comparing them. Left
+        // sides are pushed last, so they're compared first, as the witness needs.
+        let mut pending = vec![(self, other, ctx.len())];
+        while let Some((term1, term2, depth)) = pending.pop() {
+            ctx.truncate(depth);
+            match (term1, term2) {
+                // handling var: if x and y are most recently bound in the same lambda, they match
+                (Self::Var(x), Self::Var(y)) => {
+                    #[allow(clippy::map_unwrap_or)]
+                    // slight performance tradeoff, but more readable
+                    let matched = ctx
+                        .iter()
+                        .rfind(|(a, b)| a == x || b == y) // find the most recent binding of x or y
+                        .map(|(a, b)| a == x && b == y) // it should also bind the other
+                        .unwrap_or(x == y); // if neither is bound, they should be equal
+                    if !matched {
+                        return false;
+                    }
+                }
 
-            // handling appl: recurse on both sides
-            (
-                Self::Appl {
-                    left: left1,
-                    right: right1,
-                },
-                Self::Appl {
-                    left: left2,
-                    right: right2,
-                },
-            ) => left1.alpha_equiv_impl(left2, ctx) && right1.alpha_equiv_impl(right2, ctx),
+                // handling lam: store params in the ctx and compare the rules
+                (
+                    Self::Lam {
+                        param: param1,
+                        rule: rule1,
+                    },
+                    Self::Lam {
+                        param: param2,
+                        rule: rule2,
+                    },
+                ) => {
+                    ctx.push((param1, param2));
+                    if let Some(witness) = witness.as_deref_mut() {
+                        witness.push((param1, param2));
+                    }
+                    pending.push((rule1, rule2, depth + 1));
+                }
 
-            // other cases: just return false; even aside from substitution, they have different
-            // term structures
-            _ => false,
+                // handling appl: compare both sides
+                (
+                    Self::Appl {
+                        left: left1,
+                        right: right1,
+                    },
+                    Self::Appl {
+                        left: left2,
+                        right: right2,
+                    },
+                ) => {
+                    pending.push((right1, right2, depth));
+                    pending.push((left1, left2, depth));
+                }
+
+                // other cases: just return false; even aside from substitution, they have different
+                // term structures
+                _ => return false,
+            }
         }
+        true
     }
 }
 
-// global mutable state shouldn't be shared across threads (and so rust needs us to do this)
-thread_local!(static COUNTER: RefCell<usize> = 0.into());
+/// Something that can be substituted for a var: a term, or just a var name, for renaming.
+pub(crate) trait Substitute: Into<Term> + Clone {
+    /// Check whether `var` occurs free in the term this would become.
+    fn occurs_free(&self, var: &str) -> bool;
+}
+
+impl Substitute for Term {
+    fn occurs_free(&self, var: &str) -> bool {
+        Self::occurs_free(self, var)
+    }
+}
+
+impl Substitute for String {
+    fn occurs_free(&self, var: &str) -> bool {
+        self == var
+    }
+}
+
+impl Substitute for &str {
+    fn occurs_free(&self, var: &str) -> bool {
+        *self == var
+    }
+}
+
+// An atomic, rather than a thread local, so that this works without `std`. Sharing it across
+// threads is fine: it just means names are unique across threads too.
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 /// Generate a fresh variable name.
-fn get_fresh_ident(s: &str) -> String {
+pub(crate) fn get_fresh_ident(s: &str) -> String {
     // The grammar forbids variable names containing ".", so this name can't have been written by
     // the user, and the global counter ensures that specific name hasn't been generated yet by
     // this method, which is the only way new names get added to the AST.
@@ -235,15 +1396,13 @@ fn get_fresh_ident(s: &str) -> String {
     // obviously in general it's highly unlikely) that the referenced string will be next to the
     // string we're appending to the end. Returning a `String` from this function doesn't work if
     // `Term` expects a `&str`, because the reference won't live past the end of `Term::reduce`.
-    COUNTER.with(|c| {
-        *c.borrow_mut() += 1;
-        s.split('.')
-            .next()
-            .expect("split gives at least one item")
-            .to_string()
-            + "."
-            + &c.borrow().to_string()
-    })
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+    s.split('.')
+        .next()
+        .expect("split gives at least one item")
+        .to_string()
+        + "."
+        + &n.to_string()
 }
 
 #[cfg(test)]
@@ -255,6 +1414,17 @@ mod tests {
         use super::*;
         use crate::{to_term, ParserResult};
 
+        #[test]
+        /// A step says whether it found a redex, and leaves normal forms alone.
+        fn step_reports_progress() -> ParserResult<()> {
+            let mut term = to_term("x ((fn y => y) z)")?;
+            assert!(term.reduction_step());
+            assert_structural_eq!(term, to_term("x z")?);
+            assert!(!term.reduction_step());
+            assert_structural_eq!(term, to_term("x z")?);
+            Ok(())
+        }
+
         #[test]
         /// Test reducing a var.
         fn var() {
@@ -288,66 +1458,406 @@ mod tests {
                 right: "z".into(),
             };
 
-            assert_eq!(input.reduce(false), "y".into());
+            assert_eq!(input.reduce(false), "y".into());
+        }
+
+        #[test]
+        /// Test a lambda applied to another lambda.
+        fn lam_app_lam() {
+            let input = Appl {
+                left: Appl {
+                    left: Lam {
+                        param: "x".into(),
+                        rule: "x".into(),
+                    }
+                    .into(),
+                    right: Lam {
+                        param: "x".into(),
+                        rule: "x".into(),
+                    }
+                    .into(),
+                }
+                .into(),
+                right: "a".into(),
+            };
+            assert_eq!(input.reduce(false), "a".into());
+        }
+
+        #[test]
+        fn in_place() -> ParserResult<()> {
+            let mut term = to_term("(fn f => fn a => f (f a)) (fn x => x) ((fn y => y) z)")?;
+            term.reduce_in_place(false);
+            assert_eq!(term, "z".into());
+            Ok(())
+        }
+
+        #[test]
+        /// Each step should be written on its own line, not including the normal form.
+        fn to_writer() -> io::Result<()> {
+            let input = to_term("(fn x => x) ((fn y => y) z)").expect("valid term");
+            let mut out = vec![];
+            let reduced = input.reduce_to_writer(&mut out)?;
+            assert_eq!(reduced, "z".into());
+            assert_eq!(
+                String::from_utf8(out).expect("valid utf8"),
+                "(fn x => x) ((fn y => y) z)\n(fn y => y) z\n"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn to_writer_irreducible() -> io::Result<()> {
+            let mut out = vec![];
+            Term::from("x").reduce_to_writer(&mut out)?;
+            assert!(out.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        /// Substituting under a `_` doesn't rename it.
+        fn wildcard_not_renamed() -> ParserResult<()> {
+            let got = to_term("(fn y => fn _ => y) z")?.reduce(false);
+            assert_structural_eq!(got, to_term("fn _ => z")?);
+            Ok(())
+        }
+
+        // takes a name, a string representing the term to be reduced, and a string representing
+        // the expected normal form
+        macro_rules! beta_reduction_tests { ($($name:ident: $input:expr, $expected:expr)*) => {
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                // This is not a proper unit test because of the dependency on `to_term`, but it
+                // makes tests _much_ easier to develop.
+                assert!(to_term($input)?.reduce(false).equiv_str($expected)?);
+                Ok(())
+            }
+            )*
+
+            mod bench {
+                use super::to_term;
+
+                extern crate test;
+                use test::Bencher;
+                $(
+                #[bench]
+                fn $name(b: &mut Bencher) {
+                    b.iter(|| to_term($input).unwrap().reduce(false));
+                }
+                )*
+            }
+        }}
+
+        beta_reduction_tests! {
+            nested_sub: "(fn f => fn a => f) x", "fn a => x"
+            order_matters: "(fn f => fn a => f (f a)) (fn q => r) a b", "r b"
+            many_renames: "(fn f => fn y => fn x => x (y f)) y x f", "f (x y)"
+            lazy_eval: "(fn t => fn e => t) x ((fn x => x x)(fn x => x x))", "x"
+            wildcard: "(fn _ => y) z", "y"
+            nested_wildcards: "(fn _ => fn _ => fn x => x) a b c", "c"
+            y_combinator: "(fn g => ((fn y => g (y y)) (fn y => g (y y))))
+                (fn f => fn x => x q (f (fn t => fn e => t))) (fn t => fn e => e)", "q"
+            fibbit: "(fn n => (fn p => p (fn t => fn e => t)) (n (fn p => (fn a => fn b => fn s => s a b) ((fn p => p (fn t => fn e => e)) p) ((fn m => fn n => m (fn n => fn f => fn x => f (n f x)) n) ((fn p => p (fn t => fn e => t)) p) ((fn p => p (fn t => fn e => e)) p))) ((fn a => fn b => fn s => s a b) (fn f => fn x => x) ((fn n => fn f => fn x => f (n f x)) (fn f => fn x => x))))) (fn f => fn x => f (f (f (f (f (f (f (f (f (f x))))))))))", "fn f => fn x => f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f x))))))))))))))))))))))))))))))))))))))))))))))))))))))"
+        }
+    }
+
+    mod parallel {
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn step_contracts_all_redexes() -> ParserResult<()> {
+            let mut term = to_term("f ((fn x => x) a) ((fn y => (fn z => z) y) b)")?;
+            assert!(term.reduce_parallel_step());
+            assert_eq!(term, to_term("f a b")?);
+            assert!(!term.reduce_parallel_step());
+            Ok(())
+        }
+
+        #[test]
+        /// Redexes created by substitution wait for the next step.
+        fn step_leaves_new_redexes() -> ParserResult<()> {
+            let mut term = to_term("(fn f => f a) (fn x => x)")?;
+            assert!(term.reduce_parallel_step());
+            assert_eq!(term, to_term("(fn x => x) a")?);
+            Ok(())
+        }
+
+        #[test]
+        /// Independent redexes take one pass, but one normal-order step each.
+        fn fewer_passes() -> ParserResult<()> {
+            let input = "f ((fn x => x) a) ((fn x => x) b) ((fn x => x) c) ((fn x => x) d)";
+            let mut normal = to_term(input)?;
+            let mut steps = 0;
+            while normal.reduction_step() {
+                steps += 1;
+            }
+
+            let mut parallel = to_term(input)?;
+            assert!(parallel.reduce_parallel_step());
+            assert!(parallel.is_irreducible());
+            assert_eq!(parallel, normal);
+            assert_eq!(steps, 4);
+            Ok(())
+        }
+
+        macro_rules! agrees_with_normal_order_tests { ($($name:ident: $input:expr)*) => {
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let term = to_term($input)?;
+                assert!(term.clone().reduce_parallel().alpha_equiv(&term.reduce(false)));
+                Ok(())
+            }
+            )*
+
+            mod bench {
+                use super::*;
+
+                extern crate test;
+                use test::Bencher;
+                $(
+                #[bench]
+                fn $name(b: &mut Bencher) {
+                    b.iter(|| to_term($input).unwrap().reduce_parallel());
+                }
+                )*
+            }
+        }}
+
+        agrees_with_normal_order_tests! {
+            many_renames: "(fn f => fn y => fn x => x (y f)) y x f"
+            lazy_eval: "(fn t => fn e => t) x ((fn x => x x)(fn x => x x))"
+            y_combinator: "(fn g => ((fn y => g (y y)) (fn y => g (y y))))
+                (fn f => fn x => x q (f (fn t => fn e => t))) (fn t => fn e => e)"
+            fibbit: "(fn n => (fn p => p (fn t => fn e => t)) (n (fn p => (fn a => fn b => fn s => s a b) ((fn p => p (fn t => fn e => e)) p) ((fn m => fn n => m (fn n => fn f => fn x => f (n f x)) n) ((fn p => p (fn t => fn e => t)) p) ((fn p => p (fn t => fn e => e)) p))) ((fn a => fn b => fn s => s a b) (fn f => fn x => x) ((fn n => fn f => fn x => f (n f x)) (fn f => fn x => x))))) (fn f => fn x => f (f (f (f (f (f (f (f (f (f x))))))))))"
+        }
+    }
+
+    mod bounded {
+        use std::{
+            sync::atomic::{AtomicBool, Ordering},
+            thread,
+            time::Duration,
+        };
+
+        use crate::{to_term, ParserResult, Term};
+
+        #[test]
+        fn fuel_runs_out() -> ParserResult<()> {
+            let mut term = to_term("(fn x => x x) (fn x => x x)")?;
+            assert_eq!(term.reduce_with_fuel(10), 10);
+            assert_eq!(term, to_term("(fn x => x x) (fn x => x x)")?);
+            Ok(())
+        }
+
+        #[test]
+        /// Reducing in batches reaches the same normal form as reducing all at once.
+        fn fuel_batches() -> ParserResult<()> {
+            let mut term = to_term("(fn f => fn a => f (f a)) (fn f => fn a => f (f a))")?;
+            let mut total = 0;
+            loop {
+                let steps = term.reduce_with_fuel(2);
+                total += steps;
+                if steps < 2 {
+                    break;
+                }
+            }
+            assert!(term.alpha_equiv(&4.into()));
+            assert_eq!(total, 6);
+            Ok(())
+        }
+
+        #[test]
+        fn terminates() -> ParserResult<()> {
+            let term = to_term("(fn x => x) ((fn y => y) z)")?;
+            assert_eq!(term.reduce_bounded(2), Ok("z".into()));
+            Ok(())
+        }
+
+        #[test]
+        /// The cap is on the biggest term along the way, so it's exact.
+        fn size_capped() -> ParserResult<()> {
+            let term = to_term("(fn f => fn a => f (f a)) (fn f => fn a => f (f a))")?;
+            let mut biggest = term.size();
+            let mut stepped = term.clone();
+            while stepped.reduce_with_fuel(1) == 1 {
+                biggest = biggest.max(stepped.size());
+            }
+
+            let reduced = term.clone().reduce_size_capped(biggest, false);
+            assert!(reduced.expect("stays small enough").alpha_equiv(&4.into()));
+            let partial = term.reduce_size_capped(biggest - 1, false);
+            assert_eq!(partial.expect_err("grows too big").size(), biggest);
+            Ok(())
+        }
+
+        #[test]
+        /// Normal forms are fine, however big.
+        fn size_capped_normal_form() {
+            let term: Term = 10.into();
+            assert_eq!(term.clone().reduce_size_capped(1, false), Ok(term));
+        }
+
+        #[test]
+        fn timeout_terminates() -> ParserResult<()> {
+            let term = to_term("(fn f => fn a => f (f a)) (fn f => fn a => f (f a))")?;
+            let reduced = term.reduce_timeout(Duration::from_secs(10), false);
+            assert!(reduced.expect("reduces in time").alpha_equiv(&4.into()));
+            Ok(())
+        }
+
+        #[test]
+        fn timeout_runs_out() -> ParserResult<()> {
+            let term = to_term("(fn x => x x x) (fn x => x x x)")?;
+            let partial = term.reduce_timeout(Duration::from_millis(10), false);
+            // it grows every step, so it must have taken a step or two
+            assert!(partial.expect_err("diverges").size() > 13);
+            Ok(())
+        }
+
+        #[test]
+        fn cancellable_terminates() -> ParserResult<()> {
+            let term = to_term("(fn f => fn a => f (f a)) (fn f => fn a => f (f a))")?;
+            let reduced = term.reduce_cancellable(&AtomicBool::new(false), false);
+            assert!(reduced.expect("never cancelled").alpha_equiv(&4.into()));
+            Ok(())
+        }
+
+        #[test]
+        fn cancelled_before_starting() -> ParserResult<()> {
+            let term = to_term("(fn f => fn a => f (f a)) (fn f => fn a => f (f a))")?;
+            let partial = term
+                .clone()
+                .reduce_cancellable(&AtomicBool::new(true), false);
+            assert_eq!(partial, Err(term));
+            Ok(())
+        }
+
+        #[test]
+        /// Cancelling a reduction with nothing left to do still gives the normal form.
+        fn cancelled_normal_form() {
+            let term: Term = 4.into();
+            let reduced = term
+                .clone()
+                .reduce_cancellable(&AtomicBool::new(true), false);
+            assert_eq!(reduced, Ok(term));
+        }
+
+        #[test]
+        fn cancelled_from_another_thread() -> ParserResult<()> {
+            let term = to_term("(fn x => x x) (fn x => x x)")?;
+            let cancel = AtomicBool::new(false);
+            let partial = thread::scope(|s| {
+                let reduction = s.spawn(|| term.clone().reduce_cancellable(&cancel, false));
+                thread::sleep(Duration::from_millis(10));
+                cancel.store(true, Ordering::Relaxed);
+                reduction.join().expect("reduction doesn't panic")
+            });
+            assert_eq!(partial, Err(term));
+            Ok(())
+        }
+
+        #[test]
+        fn runs_out() -> ParserResult<()> {
+            let term = to_term("(fn x => x) ((fn y => y) z)")?;
+            assert_eq!(term.reduce_bounded(1), Err(to_term("(fn y => y) z")?));
+            Ok(())
+        }
+
+        #[test]
+        fn diverges() -> ParserResult<()> {
+            let omega = to_term("(fn x => x x) (fn x => x x)")?;
+            assert!(omega.reduce_bounded(100).is_err());
+            Ok(())
+        }
+
+        #[test]
+        /// Applicative order reduces the argument first.
+        fn applicative_order() -> ParserResult<()> {
+            let term = to_term("(fn x => x x) ((fn y => y) z)")?;
+            assert_eq!(
+                term.clone().reduce_bounded(1),
+                Err(to_term("((fn y => y) z) ((fn y => y) z)")?)
+            );
+            assert_eq!(
+                term.reduce_applicative_bounded(1),
+                Err(to_term("(fn x => x x) z")?)
+            );
+            Ok(())
+        }
+
+        #[test]
+        /// Normal order skips the divergent argument, applicative order doesn't.
+        fn applicative_diverges() -> ParserResult<()> {
+            let term = to_term("(fn x => y) ((fn x => x x) (fn x => x x))")?;
+            assert_eq!(term.clone().reduce_bounded(100), Ok("y".into()));
+            assert!(term.reduce_applicative_bounded(100).is_err());
+            Ok(())
+        }
+    }
+
+    mod confluence {
+        use super::*;
+        use crate::{arbitrary::TermParams, to_term, ParserResult};
+        use proptest::prelude::*;
+
+        /// How many steps to spend on a generated term before deciding it diverges.
+        const BOUND: usize = 200;
+
+        /// Generate closed terms, biased toward ones with redexes in them.
+        fn arb_term() -> impl Strategy<Value = Term> {
+            any_with::<Term>(TermParams {
+                closed: true,
+                ..TermParams::default()
+            })
         }
 
         #[test]
-        /// Test a lambda applied to another lambda.
-        fn lam_app_lam() {
-            let input = Appl {
-                left: Appl {
-                    left: Lam {
-                        param: "x".into(),
-                        rule: "x".into(),
-                    }
-                    .into(),
-                    right: Lam {
-                        param: "x".into(),
-                        rule: "x".into(),
-                    }
-                    .into(),
-                }
-                .into(),
-                right: "a".into(),
-            };
-            assert_eq!(input.reduce(false), "a".into());
+        fn confluent_with_reduct() -> ParserResult<()> {
+            let term = to_term("(fn f => fn a => f (f a)) (fn x => x) ((fn y => y) z)")?;
+            let reduct = to_term("(fn a => (fn x => x) ((fn x => x) a)) z")?;
+            assert!(term.confluent_with(&reduct));
+            Ok(())
         }
 
-        // takes a name, a string representing the term to be reduced, and a string representing
-        // the expected normal form
-        macro_rules! beta_reduction_tests { ($($name:ident: $input:expr, $expected:expr)*) => {
-            $(
-            #[test]
-            fn $name() -> ParserResult<()> {
-                // This is not a proper unit test because of the dependency on `to_term`, but it
-                // makes tests _much_ easier to develop.
-                assert!(to_term($input)?.reduce(false).alpha_equiv(&to_term($expected)?));
-                Ok(())
-            }
-            )*
+        #[test]
+        fn not_confluent_with_unrelated() -> ParserResult<()> {
+            let term = to_term("(fn x => x) z")?;
+            assert!(!term.confluent_with(&"y".into()));
+            Ok(())
+        }
 
-            mod bench {
-                use super::to_term;
+        #[test]
+        /// The two terms share the budget, so it has to cover both reductions.
+        fn equal_under_reduction_shares_budget() -> ParserResult<()> {
+            let left = to_term("(fn x => x) ((fn y => y) z)")?;
+            let right = to_term("(fn y => y) ((fn x => x) z)")?;
+            assert_eq!(left.equal_under_reduction(&right, 4), Some(true));
+            assert_eq!(left.equal_under_reduction(&right, 3), None);
+            assert_eq!(left.equal_under_reduction(&"z".into(), 2), Some(true));
+            Ok(())
+        }
 
-                extern crate test;
-                use test::Bencher;
-                $(
-                #[bench]
-                fn $name(b: &mut Bencher) {
-                    b.iter(|| to_term($input).unwrap().reduce(false));
+        proptest! {
+            #[test]
+            fn normal_and_applicative_agree(term in arb_term()) {
+                let normal = term.clone().reduce_bounded(BOUND);
+                let applicative = term.reduce_applicative_bounded(BOUND);
+                // Terms that diverge (or are just slow) under either strategy tell us nothing.
+                if let (Ok(normal), Ok(applicative)) = (normal, applicative) {
+                    prop_assert!(normal.alpha_equiv(&applicative));
                 }
-                )*
             }
-        }}
 
-        beta_reduction_tests! {
-            nested_sub: "(fn f => fn a => f) x", "fn a => x"
-            order_matters: "(fn f => fn a => f (f a)) (fn q => r) a b", "r b"
-            many_renames: "(fn f => fn y => fn x => x (y f)) y x f", "f (x y)"
-            lazy_eval: "(fn t => fn e => t) x ((fn x => x x)(fn x => x x))", "x"
-            y_combinator: "(fn g => ((fn y => g (y y)) (fn y => g (y y))))
-                (fn f => fn x => x q (f (fn t => fn e => t))) (fn t => fn e => e)", "q"
-            fibbit: "(fn n => (fn p => p (fn t => fn e => t)) (n (fn p => (fn a => fn b => fn s => s a b) ((fn p => p (fn t => fn e => e)) p) ((fn m => fn n => m (fn n => fn f => fn x => f (n f x)) n) ((fn p => p (fn t => fn e => t)) p) ((fn p => p (fn t => fn e => e)) p))) ((fn a => fn b => fn s => s a b) (fn f => fn x => x) ((fn n => fn f => fn x => f (n f x)) (fn f => fn x => x))))) (fn f => fn x => f (f (f (f (f (f (f (f (f (f x))))))))))", "fn f => fn x => f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f (f x))))))))))))))))))))))))))))))))))))))))))))))))))))))"
+            #[test]
+            fn reducts_are_confluent(term in arb_term(), steps in 0..10_usize) {
+                // Anything applicative order reaches within the bound is a reduct of `term`, and
+                // normal order finds a normal form whenever one exists.
+                if let Ok(nf) = term.clone().reduce_bounded(BOUND) {
+                    let (Ok(reduct) | Err(reduct)) = term.reduce_applicative_bounded(steps);
+                    prop_assert!(nf.confluent_with(&reduct));
+                }
+            }
         }
     }
 
@@ -421,6 +1931,397 @@ mod tests {
         }
     }
 
+    mod redexes {
+        use crate::{to_term, ParserResult};
+
+        macro_rules! redex_count_tests { ($($name:ident: $input:expr, $expected:expr)*) => {
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let term = to_term($input)?;
+                assert_eq!(term.redex_count(), $expected);
+                assert_eq!(term.contains_redex(), $expected > 0);
+                assert_eq!(term.redex_paths().len(), $expected);
+                Ok(())
+            }
+            )*
+        }}
+
+        redex_count_tests! {
+            var: "x", 0
+            lam: "fn x => x", 0
+            stuck_appl: "x (fn y => y)", 0
+            simple: "(fn x => x) y", 1
+            under_lam: "fn z => (fn x => x) z", 1
+            both_sides: "((fn x => x) a) ((fn y => y) b)", 2
+            nested: "(fn x => (fn y => y) x) ((fn z => z) w)", 3
+            omega: "(fn x => x x) (fn x => x x)", 1
+        }
+    }
+
+    mod redex_highlight {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn irreducible() -> ParserResult<()> {
+            let term = to_term("fn x => x (fn y => y)")?;
+            assert_eq!(term.display_with_redex_highlight(), term.to_string());
+            Ok(())
+        }
+
+        #[test]
+        /// The outermost redex wins over the ones inside it.
+        fn outermost() -> ParserResult<()> {
+            let term = to_term("(fn x => (fn y => y) x) ((fn z => z) w)")?;
+            assert_eq!(
+                term.display_with_redex_highlight(),
+                term.to_string().yellow().bold().to_string()
+            );
+            Ok(())
+        }
+
+        #[test]
+        /// The leftmost redex wins when neither contains the other.
+        fn leftmost() -> ParserResult<()> {
+            let term = to_term("x ((fn y => y) a) ((fn z => z) b)")?;
+            assert_eq!(
+                term.display_with_redex_highlight(),
+                format!("x ({}) ((fn z => z) b)", "(fn y => y) a".yellow().bold())
+            );
+            Ok(())
+        }
+
+        /// Contract the redex at `target` (by address), leaving the rest of the term alone.
+        fn contract_at(term: &Term, target: &Term) -> Term {
+            if ptr::eq(term, target) {
+                let mut term = term.clone();
+                term.apply();
+                return term;
+            }
+            match term {
+                Term::Var(_) => term.clone(),
+                Term::Lam { param, rule } => Term::Lam {
+                    param: param.clone(),
+                    rule: contract_at(rule, target).into(),
+                },
+                Term::Appl { left, right } => Term::Appl {
+                    left: contract_at(left, target).into(),
+                    right: contract_at(right, target).into(),
+                },
+            }
+        }
+
+        #[test]
+        /// Each highlighted redex is the one that the next step actually contracts.
+        fn matches_reduction_step() -> ParserResult<()> {
+            let mut term = to_term("(fn f => fn a => f (f a)) ((fn x => x) g) ((fn y => y) b)")?;
+            while let Some(redex) = term.next_redex() {
+                let expected = contract_at(&term, redex);
+                assert!(term.reduction_step());
+                assert!(term.alpha_equiv(&expected));
+            }
+            Ok(())
+        }
+    }
+
+    macro_rules! simplify_tests { ($($name:ident: $input:expr, $expected:expr)*) => {
+        mod simplify {
+            use crate::{to_term, ParserResult};
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                assert!(to_term($input)?.simplify().equiv_str($expected)?);
+                Ok(())
+            }
+            )*
+        }
+    }}
+
+    simplify_tests! {
+        normal_form: "fn x => f x", "fn x => f x"
+        var_arg: "(fn x => f x x) y", "f y y"
+        linear_lam_arg: "(fn f => f a) (fn y => g y)", "g a"
+        unused_lam_arg: "(fn f => a) (fn y => y y)", "a"
+        duplicated_lam_arg: "(fn f => f (f a)) (fn y => g y)", "(fn f => f (f a)) (fn y => g y)"
+        complex_arg: "(fn x => x) (f y)", "(fn x => x) (f y)"
+        omega: "(fn x => x x) (fn x => x x)", "(fn x => x x) (fn x => x x)"
+        nested: "fn a => (fn x => (fn y => x y) a) b", "fn a => b a"
+        inner_first: "(fn x => x x) ((fn y => y) z)", "z z"
+        creates_redex: "(fn f => f z) (fn y => y)", "z"
+    }
+
+    mod reduction_trace {
+        use super::*;
+        use crate::{to_term, ParserResult};
+        use Direction::{ApplLeft, ApplRight, LamBody};
+
+        #[test]
+        fn irreducible() -> ParserResult<()> {
+            assert_eq!(to_term("fn x => x y")?.reduction_trace(), vec![]);
+            Ok(())
+        }
+
+        #[test]
+        fn paths() -> ParserResult<()> {
+            let trace = to_term("fn a => f ((fn x => x) y) ((fn y => y) z)")?.reduction_trace();
+            let paths: Vec<_> = trace.into_iter().map(|(_, path)| path).collect();
+            assert_eq!(
+                paths,
+                vec![vec![LamBody, ApplLeft, ApplRight], vec![LamBody, ApplRight]]
+            );
+            Ok(())
+        }
+
+        #[test]
+        /// Contracting at each path gives the next term in the trace, then the normal form.
+        fn agrees_with_reduce() -> ParserResult<()> {
+            let term = to_term("(fn f => fn x => f (f x)) (fn y => (fn z => z) y) w")?;
+            let trace = term.clone().reduction_trace();
+            let mut terms: Vec<_> = trace.iter().skip(1).map(|(term, _)| term).collect();
+            let normal = term.reduce(false);
+            terms.push(&normal);
+            for ((term, path), next) in trace.iter().zip(terms) {
+                let mut term = term.clone();
+                assert!(term.contract_at(path));
+                assert!(term.alpha_equiv(next));
+            }
+            Ok(())
+        }
+    }
+
+    mod reduce_by {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        /// Normal order is always picking the first redex.
+        fn normal_order() -> ParserResult<()> {
+            let term = to_term("(fn f => fn x => f (f x)) (fn y => (fn z => z) y) w")?;
+            let first = term
+                .clone()
+                .reduce_by(|term| term.redex_paths().into_iter().next());
+            let next = term.clone().reduce_by(Term::next_redex_path);
+            assert_structural_eq!(first, term.clone().reduce(false));
+            assert_structural_eq!(next, term.reduce(false));
+            Ok(())
+        }
+
+        #[test]
+        fn stop_early() -> ParserResult<()> {
+            let term = to_term("(fn x => x) ((fn y => y) z)")?;
+            let mut steps = 0;
+            let out = term.reduce_by(|term| {
+                steps += 1;
+                (steps == 1).then(|| term.next_redex_path()).flatten()
+            });
+            assert_eq!(out, to_term("(fn y => y) z")?);
+            Ok(())
+        }
+
+        #[test]
+        #[should_panic(expected = "no redex")]
+        fn not_a_redex() {
+            let _ = Term::from("x").reduce_by(|_| Some(vec![]));
+        }
+    }
+
+    mod reduce_to_depth {
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        /// Redexes deeper than the limit are left alone.
+        fn leaves_deep_redexes() -> ParserResult<()> {
+            let term = to_term("fn a => f ((fn x => x) y)")?;
+            assert_structural_eq!(term.clone().reduce_to_depth(1), term);
+            assert_eq!(term.reduce_to_depth(2), to_term("fn a => f y")?);
+            Ok(())
+        }
+
+        #[test]
+        /// A contraction can bring a redex up to a depth that's in range.
+        fn raised_redexes() -> ParserResult<()> {
+            let term = to_term("(fn x => x) (fn y => (fn z => z) y)")?;
+            assert_eq!(term.reduce_to_depth(1), to_term("fn y => y")?);
+            Ok(())
+        }
+
+        #[test]
+        fn deep_enough_is_reduce() -> ParserResult<()> {
+            let term = to_term("(fn f => fn x => f (f x)) (fn y => (fn z => z) y) w")?;
+            assert_structural_eq!(
+                term.clone().reduce_to_depth(term.size()),
+                term.reduce(false)
+            );
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "log")]
+    mod log_events {
+        use std::cell::RefCell;
+
+        use log::{Level, LevelFilter, Log, Metadata, Record};
+
+        use crate::{to_term, ParserResult};
+
+        std::thread_local! {
+            static RECORDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        /// Collects the records from each thread separately, since tests run in parallel.
+        struct Collector;
+
+        impl Log for Collector {
+            fn enabled(&self, metadata: &Metadata) -> bool {
+                metadata.level() <= Level::Trace
+            }
+
+            fn log(&self, record: &Record) {
+                RECORDS.with(|records| records.borrow_mut().push(record.args().to_string()));
+            }
+
+            fn flush(&self) {}
+        }
+
+        static COLLECTOR: Collector = Collector;
+
+        #[test]
+        /// There's one event per step, matching `reduction_trace`.
+        fn one_per_step() -> ParserResult<()> {
+            let _ = log::set_logger(&COLLECTOR);
+            log::set_max_level(LevelFilter::Trace);
+
+            let term = to_term("(fn x => x) ((fn y => y) z)")?;
+            let expected: Vec<_> = term
+                .clone()
+                .reduction_trace()
+                .into_iter()
+                .map(|(term, path)| format!("{} (redex at {:?})", term, path))
+                .collect();
+            // `reduction_trace` logs its steps too
+            RECORDS.with(RefCell::take);
+            let _ = term.reduce(false);
+            assert_eq!(RECORDS.with(RefCell::take), expected);
+            Ok(())
+        }
+    }
+
+    mod reduce_stats {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn normal_form() -> ParserResult<()> {
+            let (normal, stats) = to_term("fn x => x")?.reduce_stats(0);
+            assert_eq!(normal, Some(to_term("fn x => x")?));
+            assert_eq!(
+                stats,
+                ReduceStats {
+                    steps: 0,
+                    peak_size: 2,
+                    terminated: true
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        /// The stats agree with counting steps and sizes separately.
+        fn agrees_with_trace() -> ParserResult<()> {
+            let term = to_term("(fn f => fn x => f (f x)) (fn y => y y) z")?;
+            let trace = term.clone().reduction_trace();
+            let normal = term.clone().reduce(false);
+            let peak_size = trace
+                .iter()
+                .map(|(term, _)| term.size())
+                .chain([normal.size()])
+                .max();
+
+            let (got, stats) = term.reduce_stats(100);
+            assert_eq!(got, Some(normal));
+            assert_eq!(stats.steps, trace.len());
+            assert_eq!(Some(stats.peak_size), peak_size);
+            assert!(stats.terminated);
+            Ok(())
+        }
+
+        #[test]
+        fn gives_up() -> ParserResult<()> {
+            let (normal, stats) = to_term("(fn x => x x) (fn x => x x)")?.reduce_stats(5);
+            assert_eq!(normal, None);
+            assert_eq!(stats.steps, 5);
+            assert!(!stats.terminated);
+            assert_eq!(
+                stats.to_string(),
+                "5 steps, peak size 9, no normal form yet"
+            );
+            Ok(())
+        }
+    }
+
+    mod beta_nf_or_stuck {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        macro_rules! outcome_tests { ($($name:ident: $input:expr, $stuck_on:expr)*) => {
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let term = to_term($input)?;
+                let normal_form = term.clone().reduce(false);
+                let expected: Option<&str> = $stuck_on;
+                let expected = match expected {
+                    Some(head) => ReductionOutcome::Stuck(normal_form, head.to_string()),
+                    None => ReductionOutcome::NormalForm(normal_form),
+                };
+                assert_eq!(term.beta_nf_or_stuck(), expected);
+                Ok(())
+            }
+            )*
+        }}
+
+        outcome_tests! {
+            numeral: "fn f => fn a => f (f a)", None
+            identity: "(fn x => x) (fn y => y)", None
+            bound_head: "fn x => x (fn y => y)", None
+            free_var: "x", Some("x")
+            free_head: "x (fn y => y)", Some("x")
+            under_lambda: "fn a => x a", Some("x")
+            after_reduction: "(fn f => f z) undefined", Some("undefined")
+            shadowed: "fn x => (fn x => x) x", None
+        }
+    }
+
+    mod reduce_all {
+        use super::*;
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn in_order() -> ParserResult<()> {
+            let terms = vec![
+                to_term("(fn x => x) a")?,
+                to_term("b")?,
+                to_term("(fn x => fn y => y x) c d")?,
+            ];
+            let got: Vec<_> = reduce_all(terms, false).collect();
+            assert_eq!(got, vec!["a".into(), "b".into(), to_term("d c")?]);
+            Ok(())
+        }
+
+        #[test]
+        /// A divergent term doesn't get reduced until we ask for it.
+        fn lazy() -> ParserResult<()> {
+            let terms = vec![
+                to_term("(fn x => x) a")?,
+                to_term("(fn x => x x) (fn x => x x)")?,
+            ];
+            let got: Vec<_> = reduce_all(terms, false).take(1).collect();
+            assert_eq!(got, vec!["a".into()]);
+            Ok(())
+        }
+    }
+
     mod get_fresh_ident {
         use super::*;
         use std::collections::HashSet;
@@ -573,6 +2474,88 @@ mod tests {
         }
     }
 
+    mod alpha_equiv_witness {
+        use crate::{to_term, ParserResult};
+
+        #[test]
+        fn free_vars_have_no_pairs() -> ParserResult<()> {
+            assert_eq!(
+                to_term("x y")?.alpha_equiv_witness(&to_term("x y")?),
+                Some(vec![])
+            );
+            assert_eq!(to_term("x y")?.alpha_equiv_witness(&to_term("x z")?), None);
+            Ok(())
+        }
+
+        #[test]
+        /// Pairs come in order, left subterm first, and include shadowed binders.
+        fn preorder() -> ParserResult<()> {
+            let left = to_term("(fn x => fn x => x) (fn y => y)")?;
+            let right = to_term("(fn a => fn b => b) (fn c => c)")?;
+            assert_eq!(
+                left.alpha_equiv_witness(&right),
+                Some(vec![
+                    ("x".into(), "a".into()),
+                    ("x".into(), "b".into()),
+                    ("y".into(), "c".into())
+                ])
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn agrees_with_alpha_equiv() -> ParserResult<()> {
+            let left = to_term("fn x => fn y => y")?;
+            let right = to_term("fn a => fn b => a")?;
+            assert!(!left.alpha_equiv(&right));
+            assert_eq!(left.alpha_equiv_witness(&right), None);
+            Ok(())
+        }
+    }
+
+    mod substitute_many {
+        use crate::{to_term, ParserResult, Term};
+
+        #[test]
+        /// Sequential substitution substitutes into earlier results; simultaneous doesn't.
+        fn differs_from_sequential() -> ParserResult<()> {
+            let subs = [("x".to_string(), "y".into()), ("y".to_string(), "z".into())];
+            let mut simultaneous = to_term("x y")?;
+            simultaneous.substitute_many(&subs);
+            assert_structural_eq!(simultaneous, to_term("y z")?);
+
+            let mut sequential = to_term("x y")?;
+            for (var, with) in &subs {
+                sequential.subst(var, with);
+            }
+            assert_structural_eq!(sequential, to_term("z z")?);
+            Ok(())
+        }
+
+        #[test]
+        fn avoids_capture() -> ParserResult<()> {
+            let mut term = to_term("fn y => fn z => x y z w")?;
+            term.substitute_many(&[("x".into(), "y".into()), ("w".into(), "z".into())]);
+            assert_eq!(term, to_term("fn a => fn b => y a b z")?);
+            Ok(())
+        }
+
+        #[test]
+        fn shadowed() -> ParserResult<()> {
+            let mut term = to_term("x (fn x => x y)")?;
+            term.substitute_many(&[("x".into(), "a".into()), ("y".into(), "b".into())]);
+            assert_structural_eq!(term, to_term("a (fn x => x b)")?);
+            Ok(())
+        }
+
+        #[test]
+        fn first_wins() {
+            let mut term: Term = "x".into();
+            term.substitute_many(&[("x".into(), "a".into()), ("x".into(), "b".into())]);
+            assert_structural_eq!(term, "a".into());
+        }
+    }
+
     mod subst {
         use super::*;
 
@@ -615,5 +2598,49 @@ mod tests {
             out.subst("z", &init); // z not in FV(term), so no sub necessary
             assert!(term.alpha_equiv(&out));
         }
+
+        #[test]
+        /// Binders that can't capture anything in the substituted term keep their names.
+        fn no_capture_no_rename() {
+            let mut term = Lam {
+                param: "y".into(),
+                rule: "x".into(),
+            };
+            term.subst("x", &Term::from("z"));
+            assert_eq!(
+                term,
+                Lam {
+                    param: "y".into(),
+                    rule: "z".into(),
+                }
+            );
+        }
+
+        #[test]
+        /// Binders over terms without the substituted var keep their names, even if they'd
+        /// capture something.
+        fn no_sub_no_rename() {
+            let term = Appl {
+                left: "x".into(),
+                right: Lam {
+                    param: "y".into(),
+                    rule: "z".into(),
+                }
+                .into(),
+            };
+            let mut out = term.clone();
+            out.subst("x", &Term::from("y"));
+            assert_eq!(
+                out,
+                Appl {
+                    left: "y".into(),
+                    right: Lam {
+                        param: "y".into(),
+                        rule: "z".into(),
+                    }
+                    .into(),
+                }
+            );
+        }
     }
 }
@@ -0,0 +1,242 @@
+//! Lambda terms with shared subterms.
+//!
+//! `Term` owns its children through `Box`es, so reusing a subterm means deep-cloning it. A
+//! `SharedTerm` holds its children in `Rc`s instead, so copies of a subterm share storage, and
+//! reduction only allocates new nodes along the paths it actually changes.
+use alloc::rc::Rc;
+use core::fmt::Display;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{grammar::Term, reduce::get_fresh_ident};
+
+/// A lambda term whose subterms can be shared.
+///
+/// This mirrors `Term`; convert between them with `From`, or with `Term::shared`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharedTerm {
+    Var(String),
+    Lam {
+        param: String,
+        rule: Rc<SharedTerm>,
+    },
+    Appl {
+        left: Rc<SharedTerm>,
+        right: Rc<SharedTerm>,
+    },
+}
+
+impl From<&Term> for SharedTerm {
+    fn from(term: &Term) -> Self {
+        match term {
+            Term::Var(x) => Self::Var(x.clone()),
+            Term::Lam { param, rule } => Self::Lam {
+                param: param.clone(),
+                rule: Rc::new(rule.as_ref().into()),
+            },
+            Term::Appl { left, right } => Self::Appl {
+                left: Rc::new(left.as_ref().into()),
+                right: Rc::new(right.as_ref().into()),
+            },
+        }
+    }
+}
+
+impl From<&SharedTerm> for Term {
+    fn from(term: &SharedTerm) -> Self {
+        match term {
+            SharedTerm::Var(x) => Self::Var(x.clone()),
+            SharedTerm::Lam { param, rule } => Self::Lam {
+                param: param.clone(),
+                rule: Self::from(rule.as_ref()).into(),
+            },
+            SharedTerm::Appl { left, right } => Self::Appl {
+                left: Self::from(left.as_ref()).into(),
+                right: Self::from(right.as_ref()).into(),
+            },
+        }
+    }
+}
+
+impl Display for SharedTerm {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", Term::from(self))
+    }
+}
+
+impl Term {
+    /// Convert to a `SharedTerm`.
+    #[must_use]
+    pub fn shared(&self) -> Rc<SharedTerm> {
+        Rc::new(self.into())
+    }
+}
+
+impl SharedTerm {
+    /// Perform normal-order beta reduction.
+    ///
+    /// This gives the same normal form as `Term::reduce`, up to alpha-equivalence, but
+    /// substitution is copy-on-write: subterms that a step doesn't touch are shared between the
+    /// term before and after the step, instead of cloned.
+    ///
+    /// # Safety
+    /// The halting problem is a thing. Ergo, this can cause unhandled infinite regress.
+    #[must_use]
+    pub fn reduce(self: Rc<Self>) -> Rc<Self> {
+        let mut term = self;
+        while let Some(next) = term.reduction_step() {
+            term = next;
+        }
+        term
+    }
+
+    /// Contract the leftmost-outermost redex, or return `None` if the term is irreducible.
+    ///
+    /// These are the same rules as `Term::reduction_step`.
+    fn reduction_step(&self) -> Option<Rc<Self>> {
+        match self {
+            Self::Var(_) => None,
+            Self::Lam { param, rule } => rule.reduction_step().map(|rule| {
+                Rc::new(Self::Lam {
+                    param: param.clone(),
+                    rule,
+                })
+            }),
+            Self::Appl { left, right } => {
+                if let Self::Lam { param, rule } = left.as_ref() {
+                    Some(rule.subst(param, right))
+                } else if let Some(left) = left.reduction_step() {
+                    Some(Rc::new(Self::Appl {
+                        left,
+                        right: Rc::clone(right),
+                    }))
+                } else {
+                    right.reduction_step().map(|right| {
+                        Rc::new(Self::Appl {
+                            left: Rc::clone(left),
+                            right,
+                        })
+                    })
+                }
+            }
+        }
+    }
+
+    /// Check whether `var` occurs free in the term.
+    fn occurs_free(&self, var: &str) -> bool {
+        match self {
+            Self::Var(x) => x == var,
+            Self::Lam { param, .. } if param == var => false,
+            Self::Lam { rule, .. } => rule.occurs_free(var),
+            Self::Appl { left, right } => left.occurs_free(var) || right.occurs_free(var),
+        }
+    }
+
+    /// Perform capture-avoiding substitution of `with` for `replace` in `self`.
+    ///
+    /// Any subterm that `replace` doesn't occur free in is returned as-is, so the result shares
+    /// it with `self`.
+    fn subst(self: &Rc<Self>, replace: &str, with: &Rc<Self>) -> Rc<Self> {
+        if !self.occurs_free(replace) {
+            return Rc::clone(self);
+        }
+        match self.as_ref() {
+            // `replace` occurs free, so this must be it
+            Self::Var(_) => Rc::clone(with),
+
+            Self::Lam { param, rule } => {
+                if with.occurs_free(param) {
+                    // rename the param so it can't capture anything in `with`
+                    let new = get_fresh_ident(param);
+                    let rule = rule.subst(param, &Rc::new(Self::Var(new.clone())));
+                    Rc::new(Self::Lam {
+                        param: new,
+                        rule: rule.subst(replace, with),
+                    })
+                } else {
+                    Rc::new(Self::Lam {
+                        param: param.clone(),
+                        rule: rule.subst(replace, with),
+                    })
+                }
+            }
+
+            Self::Appl { left, right } => Rc::new(Self::Appl {
+                left: left.subst(replace, with),
+                right: right.subst(replace, with),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+
+    #[test]
+    fn round_trip() -> ParserResult<()> {
+        let term = to_term("(fn f => fn a => f (f a)) (fn x => x y)")?;
+        assert_structural_eq!(Term::from(term.shared().as_ref()), term);
+        Ok(())
+    }
+
+    #[test]
+    /// Substitution shares the parts of the term it doesn't change.
+    fn untouched_subterms_are_shared() -> ParserResult<()> {
+        let term = to_term("(fn x => (fn y => y) x) z")?.shared();
+        let SharedTerm::Appl { left, .. } = term.as_ref() else {
+            unreachable!()
+        };
+        let SharedTerm::Lam { rule, .. } = left.as_ref() else {
+            unreachable!()
+        };
+        let SharedTerm::Appl { left: ident, .. } = rule.as_ref() else {
+            unreachable!()
+        };
+
+        let stepped = term.reduction_step().expect("term is reducible");
+        let SharedTerm::Appl { left, .. } = stepped.as_ref() else {
+            unreachable!()
+        };
+        assert!(Rc::ptr_eq(left, ident));
+        Ok(())
+    }
+
+    macro_rules! agrees_with_box_tests { ($($name:ident: $input:expr)*) => {
+        mod agrees_with_box {
+            use super::*;
+            $(
+            #[test]
+            fn $name() -> ParserResult<()> {
+                let term = to_term($input)?;
+                let shared = Term::from(term.shared().reduce().as_ref());
+                assert!(shared.alpha_equiv(&term.reduce(false)));
+                Ok(())
+            }
+            )*
+        }
+
+        mod bench {
+            use super::*;
+
+            extern crate test;
+            use test::Bencher;
+            $(
+            #[bench]
+            fn $name(b: &mut Bencher) {
+                b.iter(|| to_term($input).unwrap().shared().reduce());
+            }
+            )*
+        }
+    }}
+
+    agrees_with_box_tests! {
+        nested_sub: "(fn f => fn a => f) x"
+        many_renames: "(fn f => fn y => fn x => x (y f)) y x f"
+        lazy_eval: "(fn t => fn e => t) x ((fn x => x x)(fn x => x x))"
+        y_combinator: "(fn g => ((fn y => g (y y)) (fn y => g (y y))))
+            (fn f => fn x => x q (f (fn t => fn e => t))) (fn t => fn e => e)"
+        fibbit: "(fn n => (fn p => p (fn t => fn e => t)) (n (fn p => (fn a => fn b => fn s => s a b) ((fn p => p (fn t => fn e => e)) p) ((fn m => fn n => m (fn n => fn f => fn x => f (n f x)) n) ((fn p => p (fn t => fn e => t)) p) ((fn p => p (fn t => fn e => e)) p))) ((fn a => fn b => fn s => s a b) (fn f => fn x => x) ((fn n => fn f => fn x => f (n f x)) (fn f => fn x => x))))) (fn f => fn x => f (f (f (f (f (f (f (f (f (f x))))))))))"
+    }
+}
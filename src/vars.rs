@@ -0,0 +1,845 @@
+//! Bookkeeping for variable names: free variables and renaming.
+use alloc::collections::BTreeSet;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+use crate::{grammar::Term, reduce::get_fresh_ident, visit::Visitor, Set};
+
+/// Collects the free variables of a term, for `Term::free_vars`.
+#[derive(Default)]
+struct FreeVars<'a> {
+    bound: Vec<&'a str>,
+    free: BTreeSet<&'a str>,
+}
+
+impl<'a> Visitor<'a> for FreeVars<'a> {
+    fn visit_var(&mut self, name: &'a str) {
+        if !self.bound.contains(&name) {
+            self.free.insert(name);
+        }
+    }
+
+    // Same trick as in `alpha_equiv_impl`: the binding is only there while the rule is walked, so
+    // the rest of the term doesn't see it.
+    fn visit_lam(&mut self, param: &'a str, _: &'a Term) {
+        self.bound.push(param);
+    }
+
+    fn leave_lam(&mut self, _: &'a str, _: &'a Term) {
+        self.bound.pop();
+    }
+}
+
+/// How to tidy up bound variable names, e.g. once a reduction is done.
+///
+/// Reduction renames binders to fresh names like `x.12` to avoid capture, which makes normal
+/// forms hard to read. Renaming is a whole pass over the term, so it's best done once at the end
+/// rather than every step; see `Term::reduce_clean`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CleanNames {
+    /// Leave the names alone.
+    #[default]
+    Keep,
+    /// Drop the suffixes, as in `Term::strip_fresh_suffixes`.
+    StripSuffixes,
+    /// Rename every binder, as in `Term::canonicalize`.
+    Canonicalize,
+}
+
+impl Term {
+    /// Tidy up the term's bound variable names; see `CleanNames`.
+    #[must_use]
+    pub fn clean_names(self, names: CleanNames) -> Self {
+        match names {
+            CleanNames::Keep => self,
+            CleanNames::StripSuffixes => self.strip_fresh_suffixes(),
+            CleanNames::Canonicalize => self.canonicalize(),
+        }
+    }
+
+    /// Collect the free variables of the term, in order.
+    #[must_use]
+    pub fn free_vars(&self) -> BTreeSet<&str> {
+        let mut visitor = FreeVars::default();
+        self.accept(&mut visitor);
+        visitor.free
+    }
+
+    /// Check whether `var` occurs free in the term.
+    ///
+    /// Equivalent to `self.free_vars().contains(var)`, but stops at the first free occurrence
+    /// instead of collecting the whole set.
+    #[must_use]
+    pub fn occurs_free(&self, var: &str) -> bool {
+        // loop rather than recursing down rules and right sides, so long chains of appls, like
+        // big numerals, don't overflow the stack
+        let mut term = self;
+        loop {
+            term = match term {
+                Self::Var(x) => return x == var,
+
+                // a binder for `var` shadows it for the whole rule
+                Self::Lam { param, .. } if param == var => return false,
+                Self::Lam { rule, .. } => rule,
+
+                Self::Appl { left, right } => {
+                    if left.occurs_free(var) {
+                        return true;
+                    }
+                    right
+                }
+            };
+        }
+    }
+
+    /// Check whether the term has no free variables.
+    ///
+    /// Equivalent to `self.free_vars().is_empty()`, but stops at the first free variable.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// assert!(to_term("fn x => fn y => x y")?.is_closed());
+    /// assert!(!to_term("fn x => x y")?.is_closed());
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.is_closed_impl(&mut vec![])
+    }
+
+    fn is_closed_impl<'a>(&'a self, bound: &mut Vec<&'a str>) -> bool {
+        match self {
+            Self::Var(x) => bound.contains(&x.as_str()),
+
+            Self::Lam { param, rule } => {
+                bound.push(param);
+                let out = rule.is_closed_impl(bound);
+                bound.pop();
+                out
+            }
+
+            Self::Appl { left, right } => left.is_closed_impl(bound) && right.is_closed_impl(bound),
+        }
+    }
+
+    /// Count the free occurrences of `var` in the term.
+    ///
+    /// This is how many copies of the argument substituting for `var` makes, so it predicts how
+    /// much applying a lambda will grow the term.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("x (fn y => x y) (fn x => x)")?;
+    /// assert_eq!(term.count_variable_occurrences("x"), 2);
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn count_variable_occurrences(&self, var: &str) -> usize {
+        match self {
+            Self::Var(x) => usize::from(x == var),
+
+            // a binder for `var` shadows it for the whole rule
+            Self::Lam { param, .. } if param == var => 0,
+            Self::Lam { rule, .. } => rule.count_variable_occurrences(var),
+
+            Self::Appl { left, right } => {
+                left.count_variable_occurrences(var) + right.count_variable_occurrences(var)
+            }
+        }
+    }
+
+    /// Rename every free occurrence of `from` to `to`.
+    ///
+    /// This is capture-avoiding: if a free `from` is under a binder for `to`, that binder is
+    /// renamed to something fresh first, so the new `to` stays free. Bound occurrences of `from`
+    /// are left alone.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut term = to_term("x (fn x => x)")?;
+    /// term.rename_free("x", "y");
+    /// assert!(term.alpha_equiv(&to_term("y (fn x => x)")?));
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn rename_free(&mut self, from: &str, to: &str) {
+        // no need to walk the term (and freshen all its binders) if there's nothing to rename
+        if self.occurs_free(from) {
+            self.subst(from, &to);
+        }
+    }
+
+    /// Rename every binder whose name is in `avoid` to something fresh.
+    ///
+    /// Call this before splicing the term into a context, with the context's free variables as
+    /// `avoid`, and none of them can be captured by the term's binders. Unlike `subst`, which only
+    /// renames a binder once it would actually capture something, this renames them all up front.
+    /// Free variables are left alone, even if they're in `avoid`. The result is alpha-equivalent to
+    /// the original.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let mut term = to_term("fn x => fn y => x y z")?;
+    /// term.rename_to_avoid(&["y", "z"].into());
+    /// assert!(term.alpha_equiv(&to_term("fn x => fn y => x y z")?));
+    /// assert!(!term.to_string().contains("fn y "));
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn rename_to_avoid(&mut self, avoid: &BTreeSet<&str>) {
+        match self {
+            Self::Var(_) => {}
+
+            Self::Lam { param, rule } => {
+                if avoid.contains(param.as_str()) {
+                    let new = get_fresh_ident(param);
+                    rule.rename_free(param, &new);
+                    *param = new;
+                }
+                rule.rename_to_avoid(avoid);
+            }
+
+            Self::Appl { left, right } => {
+                left.rename_to_avoid(avoid);
+                right.rename_to_avoid(avoid);
+            }
+        }
+    }
+
+    /// Check that `self` is `before` with `with` substituted for `var`'s free occurrences,
+    /// without capturing anything.
+    ///
+    /// The two terms are walked side by side, like `alpha_equiv`: wherever `before` has a free
+    /// `var`, `self` must have a term alpha-equivalent to `with` none of whose free variables are
+    /// bound by the binders around it, and everywhere else the two must be alpha-equivalent.
+    /// `subst` runs this on every substitution in debug builds, and it's an oracle for fuzzing
+    /// substitution.
+    ///
+    /// # Panics
+    /// Panics if `self` isn't a capture-free substitution of `with` for `var` in `before`, e.g.
+    /// because a binder captured a free variable of `with`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::to_term;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let before = to_term("fn y => x")?;
+    /// to_term("fn z => y")?.assert_no_capture(&before, "x", &"y".into());
+    /// #
+    /// # Ok(())}
+    /// ```
+    pub fn assert_no_capture(&self, before: &Self, var: &str, with: &Self) {
+        assert!(
+            self.is_substitution_of(before, var, with),
+            "substituting `{}` for `{}` in `{}` gave `{}`, which isn't capture-free",
+            with,
+            var,
+            before,
+            self
+        );
+    }
+
+    /// Check whether `self` is `before` with `with` substituted for `var` without capture; see
+    /// `assert_no_capture`.
+    fn is_substitution_of(&self, before: &Self, var: &str, with: &Self) -> bool {
+        let with_free = with.free_vars();
+        // The context pairs up binders of `before` with the binders of `self` they became, and the
+        // stack keeps the walk iterative, both as in `alpha_equiv_impl`.
+        let mut ctx: Vec<(&str, &str)> = vec![];
+        let mut pending = vec![(self, before, 0)];
+        while let Some((after, before, depth)) = pending.pop() {
+            ctx.truncate(depth);
+            match (after, before) {
+                // a free `var` must have become `with`, with nothing in it captured
+                (_, Self::Var(x)) if x == var && !ctx.iter().any(|(old, _)| old == x) => {
+                    let captured = ctx.iter().any(|(_, new)| with_free.contains(new));
+                    if captured || !after.alpha_equiv(with) {
+                        return false;
+                    }
+                }
+
+                (Self::Var(y), Self::Var(x)) => {
+                    let matched = ctx
+                        .iter()
+                        .rfind(|(old, new)| old == x || new == y)
+                        .map_or(x == y, |(old, new)| old == x && new == y);
+                    if !matched {
+                        return false;
+                    }
+                }
+
+                (
+                    Self::Lam {
+                        param: new,
+                        rule: after,
+                    },
+                    Self::Lam {
+                        param: old,
+                        rule: before,
+                    },
+                ) => {
+                    ctx.push((old, new));
+                    pending.push((after, before, depth + 1));
+                }
+
+                (
+                    Self::Appl {
+                        left: after_left,
+                        right: after_right,
+                    },
+                    Self::Appl {
+                        left: before_left,
+                        right: before_right,
+                    },
+                ) => {
+                    pending.push((after_right, before_right, depth));
+                    pending.push((after_left, before_left, depth));
+                }
+
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Rename every bound variable to a canonical name.
+    ///
+    /// Binders are named `v0`, `v1`, ... in the order they appear in the term, skipping any name
+    /// that occurs free (so nothing gets captured). Free variables are left alone. The result is
+    /// alpha-equivalent to the original, and any two alpha-equivalent terms canonicalize to
+    /// structurally equal terms, so `a.canonicalize().structural_eq(&b.canonicalize())` is an
+    /// alternative to `a.alpha_equiv(&b)`.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("fn x => fn y => x v0")?;
+    /// assert!(term.canonicalize().structural_eq(&to_term("fn v1 => fn v2 => v1 v0")?));
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn canonicalize(&self) -> Self {
+        let free = self.free_vars();
+        self.canonicalize_impl(&free, &mut vec![], &mut 0)
+    }
+
+    fn canonicalize_impl<'a>(
+        &'a self,
+        free: &BTreeSet<&str>,
+        ctx: &mut Vec<(&'a str, String)>,
+        next: &mut usize,
+    ) -> Self {
+        match self {
+            // bound vars take the name of their most recent binding, free vars stay put
+            Self::Var(x) => ctx
+                .iter()
+                .rfind(|(old, _)| old == x)
+                .map_or_else(|| x.clone(), |(_, new)| new.clone())
+                .into(),
+
+            Self::Lam { param, rule } => {
+                let new = loop {
+                    let candidate = format!("v{}", next);
+                    *next += 1;
+                    if !free.contains(candidate.as_str()) {
+                        break candidate;
+                    }
+                };
+                ctx.push((param, new.clone()));
+                let rule = rule.canonicalize_impl(free, ctx, next);
+                ctx.pop();
+                Self::Lam {
+                    param: new,
+                    rule: rule.into(),
+                }
+            }
+
+            Self::Appl { left, right } => Self::Appl {
+                left: left.canonicalize_impl(free, ctx, next).into(),
+                right: right.canonicalize_impl(free, ctx, next).into(),
+            },
+        }
+    }
+
+    /// Rename bound variables to drop the `.N` suffixes that reduction adds.
+    ///
+    /// Each binder gets the name it had before reduction renamed it, unless that would capture a
+    /// variable in its body, in which case it gets a number appended (`x1`, `x2`, ...) instead.
+    /// Free variables are left alone. The result is alpha-equivalent to the original.
+    ///
+    /// # Example
+    /// ```
+    /// # use m3lc::{to_term, Term};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #
+    /// let term = to_term("(fn f => fn y => fn x => x (y f)) y")?.reduce(false);
+    /// assert!(term.strip_fresh_suffixes().structural_eq(&to_term("fn y1 => fn x => x (y1 y)")?));
+    /// #
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn strip_fresh_suffixes(&self) -> Self {
+        self.strip_fresh_suffixes_impl(&mut vec![])
+    }
+
+    fn strip_fresh_suffixes_impl<'a>(&'a self, ctx: &mut Vec<(&'a str, String)>) -> Self {
+        // the name a var is printed as, given the renamings in `ctx`
+        let renamed = |ctx: &[(&str, String)], x: &'a str| -> String {
+            ctx.iter()
+                .rfind(|(old, _)| *old == x)
+                .map_or_else(|| x.to_string(), |(_, new)| new.clone())
+        };
+
+        match self {
+            Self::Var(x) => renamed(ctx, x).into(),
+
+            Self::Lam { param, rule } => {
+                // The new name can't be the new name of anything else that's free in the body,
+                // or it would capture it.
+                let taken: Set<String> = rule
+                    .free_vars()
+                    .into_iter()
+                    .filter(|x| x != param)
+                    .map(|x| renamed(ctx, x))
+                    .collect();
+                let base = param
+                    .split('.')
+                    .next()
+                    .expect("split gives at least one item");
+                let mut new = base.to_string();
+                let mut suffix = 0;
+                while taken.contains(&new) {
+                    suffix += 1;
+                    new = format!("{}{}", base, suffix);
+                }
+
+                ctx.push((param, new.clone()));
+                let rule = rule.strip_fresh_suffixes_impl(ctx);
+                ctx.pop();
+                Self::Lam {
+                    param: new,
+                    rule: rule.into(),
+                }
+            }
+
+            Self::Appl { left, right } => Self::Appl {
+                left: left.strip_fresh_suffixes_impl(ctx).into(),
+                right: right.strip_fresh_suffixes_impl(ctx).into(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+
+    mod free_vars {
+        use super::*;
+
+        #[test]
+        fn var() {
+            assert_eq!(Term::from("x").free_vars(), BTreeSet::from(["x"]));
+        }
+
+        #[test]
+        fn closed() -> ParserResult<()> {
+            assert!(to_term("fn f => fn a => f (f a)")?.free_vars().is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn mixed() -> ParserResult<()> {
+            let term = to_term("(fn x => x y) (fn y => x z) y")?;
+            assert_eq!(term.free_vars(), BTreeSet::from(["x", "y", "z"]));
+            Ok(())
+        }
+
+        #[test]
+        fn shadowed() -> ParserResult<()> {
+            let term = to_term("fn x => (fn x => x) x")?;
+            assert!(term.free_vars().is_empty());
+            Ok(())
+        }
+    }
+
+    mod occurs_free {
+        use super::*;
+
+        #[test]
+        fn var() {
+            assert!(Term::from("x").occurs_free("x"));
+            assert!(!Term::from("x").occurs_free("y"));
+        }
+
+        #[test]
+        fn bound() -> ParserResult<()> {
+            assert!(!to_term("fn x => x")?.occurs_free("x"));
+            Ok(())
+        }
+
+        #[test]
+        fn shadowed_then_free() -> ParserResult<()> {
+            let term = to_term("(fn x => x) x")?;
+            assert!(term.occurs_free("x"));
+            Ok(())
+        }
+
+        #[test]
+        fn under_other_binder() -> ParserResult<()> {
+            let term = to_term("fn y => fn z => y x")?;
+            assert!(term.occurs_free("x"));
+            assert!(!term.occurs_free("y"));
+            Ok(())
+        }
+
+        #[test]
+        fn agrees_with_free_vars() -> ParserResult<()> {
+            let term = to_term("(fn x => x y) (fn y => x z) y")?;
+            for var in ["x", "y", "z", "w"] {
+                assert_eq!(term.occurs_free(var), term.free_vars().contains(var));
+            }
+            Ok(())
+        }
+    }
+
+    mod is_closed {
+        use super::*;
+
+        #[test]
+        fn var() {
+            assert!(!Term::from("x").is_closed());
+        }
+
+        #[test]
+        fn shadowed_then_free() -> ParserResult<()> {
+            assert!(!to_term("(fn x => x) x")?.is_closed());
+            assert!(to_term("fn x => (fn x => x) x")?.is_closed());
+            Ok(())
+        }
+
+        #[test]
+        fn agrees_with_free_vars() -> ParserResult<()> {
+            for input in [
+                "fn f => fn a => f (f a)",
+                "fn f => g f",
+                "(fn x => x) (fn y => x)",
+            ] {
+                let term = to_term(input)?;
+                assert_eq!(term.is_closed(), term.free_vars().is_empty());
+            }
+            Ok(())
+        }
+    }
+
+    mod count_variable_occurrences {
+        use super::*;
+
+        #[test]
+        fn none() -> ParserResult<()> {
+            assert_eq!(to_term("fn x => x y")?.count_variable_occurrences("x"), 0);
+            Ok(())
+        }
+
+        #[test]
+        fn shadowed() -> ParserResult<()> {
+            let term = to_term("x x (fn x => x x) (fn y => x)")?;
+            assert_eq!(term.count_variable_occurrences("x"), 3);
+            Ok(())
+        }
+
+        #[test]
+        fn agrees_with_occurs_free() -> ParserResult<()> {
+            let term = to_term("(fn x => x y) (fn y => x z) y")?;
+            for var in ["x", "y", "z", "w"] {
+                assert_eq!(
+                    term.count_variable_occurrences(var) > 0,
+                    term.occurs_free(var)
+                );
+            }
+            Ok(())
+        }
+    }
+
+    mod assert_no_capture {
+        use super::*;
+
+        #[test]
+        fn renamed() -> ParserResult<()> {
+            let after = to_term("fn y1 => y y1")?;
+            after.assert_no_capture(&to_term("fn y => x y")?, "x", &"y".into());
+            Ok(())
+        }
+
+        #[test]
+        fn var_not_free() -> ParserResult<()> {
+            let before = to_term("fn x => x")?;
+            before.assert_no_capture(&before, "x", &"y".into());
+            Ok(())
+        }
+
+        #[test]
+        fn shadowed() -> ParserResult<()> {
+            let after = to_term("y (fn y1 => y)")?;
+            after.assert_no_capture(&to_term("y (fn y => x)")?, "x", &"y".into());
+            Ok(())
+        }
+
+        #[test]
+        #[should_panic(expected = "substituting `y` for `x`")]
+        /// The free variables are right, since `y` was free already, but the inner `y` is bound.
+        fn captured_alongside_free() {
+            let before = to_term("y (fn y => x)").expect("valid term");
+            let after = to_term("y (fn y => y)").expect("valid term");
+            after.assert_no_capture(&before, "x", &"y".into());
+        }
+
+        #[test]
+        #[should_panic(expected = "substituting `y` for `x`")]
+        /// Substituting into a binder's own name is wrong even if it captures nothing.
+        fn wrong_position() {
+            let before = to_term("x (fn x => x)").expect("valid term");
+            let after = to_term("y (fn x => y)").expect("valid term");
+            after.assert_no_capture(&before, "x", &"y".into());
+        }
+
+        #[test]
+        #[should_panic(expected = "substituting `y` for `x`")]
+        fn captured() {
+            let before = to_term("fn y => x y").expect("valid term");
+            let after = to_term("fn y => y y").expect("valid term");
+            after.assert_no_capture(&before, "x", &"y".into());
+        }
+    }
+
+    mod rename_free {
+        use super::*;
+
+        #[test]
+        fn free_only() -> ParserResult<()> {
+            let mut term = to_term("x (fn x => x) (fn y => x)")?;
+            term.rename_free("x", "z");
+            assert!(term.equiv_str("z (fn x => x) (fn y => z)")?);
+            Ok(())
+        }
+
+        #[test]
+        fn not_free() -> ParserResult<()> {
+            let mut term = to_term("fn x => x y")?;
+            term.rename_free("x", "z");
+            assert_structural_eq!(term, to_term("fn x => x y")?);
+            Ok(())
+        }
+
+        #[test]
+        /// An inner binder for `to` would capture the renamed var, so it gets freshened.
+        fn avoids_capture() -> ParserResult<()> {
+            let mut term = to_term("fn y => x y")?;
+            term.rename_free("x", "y");
+            assert!(term.occurs_free("y"));
+            assert!(!term.occurs_free("x"));
+            let Term::Lam { param, .. } = &term else {
+                panic!("renaming doesn't change the term's structure");
+            };
+            assert_ne!(param, "y");
+            assert!(term.equiv_str("fn z => y z")?);
+            Ok(())
+        }
+    }
+
+    mod rename_to_avoid {
+        use super::*;
+
+        /// The term's binders, in order.
+        fn params(term: &Term) -> Vec<String> {
+            term.fold(
+                |_| vec![],
+                |param, mut params| {
+                    params.insert(0, param.to_string());
+                    params
+                },
+                |mut left, right| {
+                    left.extend(right);
+                    left
+                },
+            )
+        }
+
+        #[test]
+        fn renames_bound() -> ParserResult<()> {
+            let original = to_term("fn x => (fn y => x y) (fn x => x)")?;
+            let mut term = original.clone();
+            term.rename_to_avoid(&BTreeSet::from(["x"]));
+            assert_eq!(term, original);
+            let params = params(&term);
+            assert_ne!(params[0], "x");
+            assert_eq!(params[1], "y");
+            assert_ne!(params[2], "x");
+            Ok(())
+        }
+
+        #[test]
+        fn free_untouched() -> ParserResult<()> {
+            let mut term = to_term("x (fn y => y z)")?;
+            term.rename_to_avoid(&BTreeSet::from(["x", "z"]));
+            assert_structural_eq!(term, to_term("x (fn y => y z)")?);
+            Ok(())
+        }
+
+        #[test]
+        /// Occurrences bound by a shadowing binder follow that binder, not the outer one.
+        fn shadowed() -> ParserResult<()> {
+            let mut term = to_term("fn x => x (fn x => x)")?;
+            term.rename_to_avoid(&BTreeSet::from(["x"]));
+            assert!(term.equiv_str("fn a => a (fn b => b)")?);
+            assert!(!params(&term).contains(&"x".to_string()));
+            Ok(())
+        }
+    }
+
+    mod clean_names {
+        use super::*;
+
+        #[test]
+        fn each() -> ParserResult<()> {
+            let term = to_term("(fn x => fn y => x y) y")?.reduce(false);
+            let kept = term.clone().clean_names(CleanNames::Keep);
+            assert_structural_eq!(kept, term);
+            let stripped = term.clone().clean_names(CleanNames::StripSuffixes);
+            assert_structural_eq!(stripped, to_term("fn y1 => y y1")?);
+            let canonical = term.clean_names(CleanNames::Canonicalize);
+            assert_structural_eq!(canonical, to_term("fn v0 => y v0")?);
+            Ok(())
+        }
+    }
+
+    mod canonicalize {
+        use super::*;
+
+        #[test]
+        fn identity() -> ParserResult<()> {
+            assert_structural_eq!(
+                to_term("fn x => x")?.canonicalize(),
+                to_term("fn v0 => v0")?
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn alpha_equivalent_terms_agree() -> ParserResult<()> {
+            let left = to_term("fn f => fn a => f (fn f => f a)")?;
+            let right = to_term("fn g => fn b => g (fn h => h b)")?;
+            assert_structural_eq!(left.canonicalize(), right.canonicalize());
+            Ok(())
+        }
+
+        #[test]
+        fn different_terms_disagree() -> ParserResult<()> {
+            let left = to_term("fn x => fn y => x")?;
+            let right = to_term("fn x => fn y => y")?;
+            assert!(!left.canonicalize().structural_eq(&right.canonicalize()));
+            Ok(())
+        }
+
+        #[test]
+        fn free_vars_untouched() -> ParserResult<()> {
+            let term = to_term("fn x => x y")?;
+            assert_structural_eq!(term.canonicalize(), to_term("fn v0 => v0 y")?);
+            Ok(())
+        }
+
+        #[test]
+        /// A canonical name that's free in the term would capture it, so it gets skipped.
+        fn avoids_capture() -> ParserResult<()> {
+            let term = to_term("fn x => fn y => v1 x")?;
+            let canonical = term.canonicalize();
+            assert_structural_eq!(canonical, to_term("fn v0 => fn v2 => v1 v0")?);
+            assert!(canonical.alpha_equiv(&term));
+            Ok(())
+        }
+
+        #[test]
+        fn preserves_alpha_equivalence() -> ParserResult<()> {
+            let term = to_term("(fn g => ((fn y => g (y y)) (fn y => g (y y)))) (fn x => x)")?;
+            assert!(term.canonicalize().alpha_equiv(&term));
+            Ok(())
+        }
+    }
+
+    mod strip_fresh_suffixes {
+        use super::*;
+
+        #[test]
+        fn simple() {
+            let term = Term::Lam {
+                param: "x.47".into(),
+                rule: "x.47".into(),
+            };
+            assert_structural_eq!(
+                term.strip_fresh_suffixes(),
+                Term::lambda(&["x"], "x".into())
+            );
+        }
+
+        #[test]
+        /// Shadowing is fine as long as nothing gets captured.
+        fn shadowing() {
+            let term = Term::lambda(&["x.1", "x.2"], "x.2".into());
+            assert_structural_eq!(
+                term.strip_fresh_suffixes(),
+                Term::lambda(&["x", "x"], "x".into())
+            );
+        }
+
+        #[test]
+        fn avoids_capturing_free() {
+            let term = Term::lambda(&["y.3"], Term::apply_all("y.3".into(), vec!["y".into()]));
+            assert_structural_eq!(
+                term.strip_fresh_suffixes(),
+                Term::lambda(&["y1"], Term::apply_all("y1".into(), vec!["y".into()]))
+            );
+        }
+
+        #[test]
+        fn avoids_capturing_bound() {
+            let term = Term::lambda(
+                &["x.1", "x.2"],
+                Term::apply_all("x.1".into(), vec!["x.2".into()]),
+            );
+            let got = term.strip_fresh_suffixes();
+            assert_structural_eq!(
+                got,
+                Term::lambda(&["x", "x1"], Term::apply_all("x".into(), vec!["x1".into()]))
+            );
+        }
+
+        #[test]
+        fn after_reduction() -> ParserResult<()> {
+            let term = to_term("(fn f => fn y => fn x => x (y f)) y x")?.reduce(false);
+            let got = term.strip_fresh_suffixes();
+            assert!(got.alpha_equiv(&term));
+            assert_structural_eq!(got, to_term("fn x1 => x1 (x y)")?);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! A visitor for writing analyses of terms without recursing by hand.
+use crate::grammar::Term;
+
+/// Hooks called while walking a term with `Term::accept`.
+///
+/// Every hook does nothing by default, so a visitor only implements the ones it cares about. The
+/// walk is preorder and left to right; `leave_lam` is called once a lambda's rule has been
+/// walked, which is what's needed to keep track of which vars are bound.
+///
+/// # Example
+/// ```
+/// # use m3lc::{to_term, Visitor};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #
+/// struct LamCount(usize);
+///
+/// impl Visitor<'_> for LamCount {
+///     fn visit_lam(&mut self, _: &str, _: &m3lc::Term) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let mut count = LamCount(0);
+/// to_term("fn f => fn a => f ((fn x => x) a)")?.accept(&mut count);
+/// assert_eq!(count.0, 3);
+/// #
+/// # Ok(())}
+/// ```
+pub trait Visitor<'a> {
+    /// Called on each var.
+    fn visit_var(&mut self, _name: &'a str) {}
+
+    /// Called on each lambda, before its rule is walked.
+    fn visit_lam(&mut self, _param: &'a str, _rule: &'a Term) {}
+
+    /// Called on each lambda, after its rule is walked.
+    fn leave_lam(&mut self, _param: &'a str, _rule: &'a Term) {}
+
+    /// Called on each application, before its left and right sides are walked.
+    fn visit_appl(&mut self, _left: &'a Term, _right: &'a Term) {}
+}
+
+impl Term {
+    /// Walk the term, calling `visitor`'s hooks on each subterm.
+    pub fn accept<'a, V: Visitor<'a> + ?Sized>(&'a self, visitor: &mut V) {
+        // loop down right sides rather than recursing, so long chains of appls, like big
+        // numerals, don't overflow the stack
+        let mut term = self;
+        loop {
+            match term {
+                Self::Var(x) => return visitor.visit_var(x),
+                Self::Lam { param, rule } => {
+                    visitor.visit_lam(param, rule);
+                    rule.accept(visitor);
+                    return visitor.leave_lam(param, rule);
+                }
+                Self::Appl { left, right } => {
+                    visitor.visit_appl(left, right);
+                    left.accept(visitor);
+                    term = right;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_term, ParserResult};
+
+    /// Record the order the hooks are called in.
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Visitor<'_> for Events {
+        fn visit_var(&mut self, name: &str) {
+            self.0.push(name.to_string());
+        }
+
+        fn visit_lam(&mut self, param: &str, _: &Term) {
+            self.0.push(format!("fn {}", param));
+        }
+
+        fn leave_lam(&mut self, param: &str, _: &Term) {
+            self.0.push(format!("end {}", param));
+        }
+
+        fn visit_appl(&mut self, _: &Term, _: &Term) {
+            self.0.push("appl".to_string());
+        }
+    }
+
+    #[test]
+    fn order() -> ParserResult<()> {
+        let mut events = Events::default();
+        to_term("fn x => (fn y => y) x z")?.accept(&mut events);
+        assert_eq!(
+            events.0,
+            ["fn x", "appl", "appl", "fn y", "y", "end y", "x", "z", "end x"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    /// Unimplemented hooks do nothing.
+    fn defaults() -> ParserResult<()> {
+        struct Vars(usize);
+        impl Visitor<'_> for Vars {
+            fn visit_var(&mut self, _: &str) {
+                self.0 += 1;
+            }
+        }
+
+        let mut vars = Vars(0);
+        to_term("fn f => fn a => f (f a)")?.accept(&mut vars);
+        assert_eq!(vars.0, 3);
+        Ok(())
+    }
+}
@@ -6,12 +6,18 @@ let b:current_syntax = "m3lc"
 
 syn match lcOperator "\v\:\="
 syn match lcOperator "\v\=\>"
-syn keyword lcKeyword fn
+syn keyword lcKeyword fn letrec in
+syn match lcInclude "\v^\s*include\ze\s*\""
+syn region lcString start=/"/ end=/"/ oneline
+syn match lcNumber "\v<0(x\x+|b[01]+)>"
 syn keyword lcTodo TODO
 syn match lcComment "\v#.*$" contains=lcTodo
 
 highlight def link lcFunction Function
 highlight def link lcKeyword Keyword
+highlight def link lcInclude Include
+highlight def link lcString String
+highlight def link lcNumber Number
 highlight def link lcOperator Operator
 highlight def link lcComment Comment
 highlight def link lcTodo Todo